@@ -25,6 +25,7 @@ pub struct Cli {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum Commands {
     /// Install a ClickHouse version
     #[command(after_help = "\
@@ -88,10 +89,23 @@ CONTEXT FOR AGENTS:
     #[command(after_help = "\
 CONTEXT FOR AGENTS:
   Creates a .clickhouse/ directory (runtime data, git-ignored) and a clickhouse/ project
-  scaffold with subdirs: tables/, materialized_views/, queries/, seed/ (each with .gitkeep).
+  scaffold with subdirs: tables/, materialized_views/, migrations/, queries/, seed/, tests/ (each with .gitkeep).
   The clickhouse/ directory is meant to be committed — organize your SQL files there.
-  Related: `chv run server` to start a server with project-local data.")]
-    Init,
+  --template fills the scaffold with a starter project: built-in events (event analytics),
+  logs (observability), metrics (time series), or a path to a directory laid out like clickhouse/.
+  Templates never overwrite existing files. --list-templates shows the built-in ones.
+  Each built-in template has tables, a materialized view rollup, saved queries, seed data and tests.
+  Related: `chv run server` to start a server with project-local data,
+  `chv schema apply` + `chv seed` to load a template, `chv test` to run its tests.")]
+    Init {
+        /// Built-in template name or path to a template directory
+        #[arg(long)]
+        template: Option<String>,
+
+        /// List the built-in templates
+        #[arg(long)]
+        list_templates: bool,
+    },
 
     /// Run ClickHouse commands
     #[command(after_help = "\
@@ -103,6 +117,211 @@ CONTEXT FOR AGENTS:
   Related: `chv use <version>` to set default, `chv which` to check current version.")]
     Run(RunArgs),
 
+    /// Show logs of the project's local server
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Reads clickhouse-server.log from .clickhouse/{version}/ for the default version.
+  Filters: --level (that level and more severe), --since (e.g. 10m, 2h), --grep (substring).
+  Prints the last --lines matching records (default 100); --follow keeps streaming new ones.
+  --json emits one JSON object per record: timestamp, thread_id, query_id, level, logger, message.
+  Related: `chv run server` writes these logs.")]
+    Logs {
+        /// Keep printing new log records as they are written
+        #[arg(long, short)]
+        follow: bool,
+
+        /// Minimum severity: fatal, critical, error, warning, notice, information, debug, trace
+        #[arg(long)]
+        level: Option<String>,
+
+        /// Only show records newer than this (e.g. 30s, 10m, 2h, 1d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only show records containing this text
+        #[arg(long)]
+        grep: Option<String>,
+
+        /// Number of matching records to show before following
+        #[arg(long, short = 'n', default_value_t = 100)]
+        lines: usize,
+
+        /// Output records as JSON lines
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Manage project data directories
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Project data in .clickhouse/ is scoped by version (.clickhouse/{version}/).
+  Subcommands: migrate (carry data to another version).
+  Related: `chv use <version>` to switch versions.")]
+    Data {
+        #[command(subcommand)]
+        command: DataCommands,
+    },
+
+    /// Save and restore named snapshots of project data
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Snapshots copy .clickhouse/{version}/ for the default version into .clickhouse/snapshots/{name}/.
+  Subcommands: save, restore, list, rm.
+  Each snapshot records the version, size and creation time; restore refuses a snapshot from
+  another version and refuses to run while the project's server is running.
+  Typical: `chv snapshot save baseline` → experiment → stop server → `chv snapshot restore baseline`.
+  Related: `chv data migrate` to move data between versions.")]
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+
+    /// Work with the project's table and view definitions
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Operates on SQL files in clickhouse/tables/ and clickhouse/materialized_views/ (plus views/ and
+  dictionaries/ when present).
+  Subcommands: apply (execute them against a server in dependency order),
+  diff (compare them with what exists on a server), dump (write a server's objects into files),
+  graph (show how tables, views and materialized views feed each other).
+  Connects to localhost:9000 by default (the server from `chv run server`); override with --host/--port.
+  Related: `chv init` to create the scaffold, `chv run server` to start a server.")]
+    Schema {
+        #[command(subcommand)]
+        command: SchemaCommands,
+    },
+
+    /// Apply versioned SQL migrations
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Migrations live in clickhouse/migrations/ as {timestamp}_{name}.up.sql and {timestamp}_{name}.down.sql.
+  Subcommands: new, up, down, status.
+  Applied migrations are recorded in the chv_migrations table on the target server, with a
+  checksum of each up file; `up` refuses to run if an applied migration was edited afterwards.
+  Connects to localhost:9000 by default (the server from `chv run server`); override with --host/--port.
+  Related: `chv schema apply` for the declarative table/view definitions.")]
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommands,
+    },
+
+    /// Load seed data from clickhouse/seed/ into a server
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Processes files in clickhouse/seed/ in lexical order (prefix .sql names with 01_, 02_ to control it):
+    *.sql                       executed statement by statement
+    *.csv, *.tsv                loaded with a header row (CSVWithNames/TSVWithNames)
+    *.jsonl                     loaded as JSONEachRow
+    *.parquet                   loaded as Parquet
+  Data files go into the table named by the unchanged file stem: events.csv → events,
+  db.events.csv → db.events, 2024_sales.csv → 2024_sales.
+  --truncate empties every table the seed files write to before loading.
+  Prints rows inserted per table at the end. Tables must exist (run `chv schema apply` first).
+  Connects to localhost:9000 by default (the server from `chv run server`); override with --host/--port.
+  Related: `chv schema apply`, `chv migrate up`.")]
+    Seed {
+        #[command(flatten)]
+        conn: ConnectionArgs,
+
+        /// Truncate target tables before loading
+        #[arg(long)]
+        truncate: bool,
+    },
+
+    /// Run a saved query from clickhouse/queries/
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Runs clickhouse/queries/{name}.sql (subdirectories allowed: `chv query reports/daily`).
+  Binds ClickHouse query parameters declared in the file as {key:Type} with --param key=value;
+  every declared parameter must be given, and unknown ones are rejected.
+  Runs against the local server (localhost:9000, override with --host/--port) or, with --local,
+  through clickhouse-local (no server needed; useful with file()/url() table functions).
+  --list shows saved queries with their parameters and the first-line comment as description.
+  Related: `chv run server` to start the local server.")]
+    Query {
+        /// Saved query name (file name without .sql)
+        #[arg(required_unless_present = "list")]
+        name: Option<String>,
+
+        /// Query parameter as key=value (repeatable)
+        #[arg(long = "param", value_name = "KEY=VALUE")]
+        params: Vec<String>,
+
+        /// Output format
+        #[arg(long, default_value = "PrettyCompact")]
+        format: String,
+
+        /// Run through clickhouse-local instead of the server
+        #[arg(long)]
+        local: bool,
+
+        /// List saved queries and their parameters
+        #[arg(long)]
+        list: bool,
+
+        #[command(flatten)]
+        conn: ConnectionArgs,
+    },
+
+    /// Run SQL regression tests from clickhouse/tests/
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Each clickhouse/tests/NAME.sql is run in a fresh clickhouse-local session after loading the
+  project schema (tables/, dictionaries/, views/, materialized_views/) and seed (seed/).
+  Its output (TSV by default; use FORMAT in the query to change it) is compared with NAME.reference
+  and differences are printed as a line diff. Exits non-zero if any test fails.
+  --update writes the actual output to NAME.reference instead (creates missing references).
+  Pass test names to run a subset: `chv test daily_rollup`.
+  Related: `chv seed` to load the same seed into a server.")]
+    Test {
+        /// Tests to run (file names without .sql); all tests by default
+        names: Vec<String>,
+
+        /// Regenerate .reference files from the actual output
+        #[arg(long)]
+        update: bool,
+    },
+
+    /// Format the project's SQL files with clickhouse format
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Rewrites every .sql file below clickhouse/ statement by statement with the default version's
+  `clickhouse format`. Comments and blank lines between statements are kept; statements that
+  contain comments are left as written (clickhouse format would drop the comments).
+  --check changes nothing, lists files that would be reformatted and exits non-zero if any (for CI).
+  Related: `chv lint` to check for parse errors and risky definitions.")]
+    Fmt {
+        /// Report files that are not formatted instead of rewriting them
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Check the project's SQL files for errors and risky definitions
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Parses every statement in every .sql file below clickhouse/ with `clickhouse format` and
+  reports problems as file:line: [rule] message. Rules:
+    parse-error         statement does not parse
+    no-order-by         MergeTree-family table without ORDER BY (or with ORDER BY tuple())
+    nullable-sort-key   Nullable column used in ORDER BY / PRIMARY KEY
+    mv-without-to       materialized view without an explicit TO table
+  Exits non-zero if any problem is found.
+  Related: `chv fmt` to format the same files.")]
+    Lint,
+
+    /// Manage a local ClickHouse Keeper
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Runs a single-node ClickHouse Keeper in the background for testing replicated tables locally.
+  Uses the default version's binary with a generated config; data lives in .clickhouse/keeper/.
+  Listens on 127.0.0.1:9181. Subcommands: start, stop, status.
+  `chv run server --keeper` starts Keeper if needed and wires the server's zookeeper section to it.
+  Related: `chv run server --keeper` to use it from a local server.")]
+    Keeper {
+        #[command(subcommand)]
+        command: KeeperCommands,
+    },
+
     /// ClickHouse Cloud API commands
     #[command(after_help = "\
 CONTEXT FOR AGENTS:
@@ -110,8 +329,16 @@ CONTEXT FOR AGENTS:
   Gateway to org/service/backup subcommands for ClickHouse Cloud.
   Auth: `chv cloud auth` to save credentials interactively (stored in .clickhouse/credentials.json).
   Or use env vars CLICKHOUSE_CLOUD_API_KEY + CLICKHOUSE_CLOUD_API_SECRET, or --api-key/--api-secret flags.
+  The API base URL defaults to https://api.clickhouse.cloud/v1; override it with --api-url,
+  an api_url field in .clickhouse/credentials.json, or CLICKHOUSE_CLOUD_API_URL.
   Verify auth with `chv cloud org list`.
-  Add --json to any cloud command for machine-readable output.
+  Add --json to any cloud command for machine-readable output; failures then print
+  {\"error\": {message, exit_code, status, code, request_id}} to stdout.
+  Exit codes: 1 other error, 3 unauthorized/forbidden, 4 not found, 5 rate limited,
+  6 API unavailable (5xx, timeout, connection failure), 7 request rejected (other 4xx).
+  Reads, deletes and start/stop retry 429/5xx/timeouts with backoff (honours Retry-After);
+  `service create` is only retried when the API cannot have processed it (429, connect failure).
+  Tune with --max-retries (0 disables), --connect-timeout and --request-timeout (seconds).
   Typical workflow: `cloud org list` → get org ID → `cloud service list` → manage services.
   Related: `chv cloud org list` to start.")]
     Cloud(CloudArgs),
@@ -136,8 +363,19 @@ CONTEXT FOR AGENTS:
   Auto-initializes the data directory on first run. Replaces the current process (exec).
   Pass extra clickhouse-server args after -- (e.g., `chv run server -- --http_port=9000`).
   Data persists in .clickhouse/{version}/ between runs.
+  --keeper starts a local Keeper (if not running) and points the server's zookeeper config at it.
+  Refuses to open a data directory last written by a newer ClickHouse version (see .clickhouse/{version}/chv-version.json)
+  unless --allow-downgrade is given.
   Related: `chv run client` to connect, `chv use <version>` to change version.")]
     Server {
+        /// Connect the server to the local Keeper (started automatically if needed)
+        #[arg(long)]
+        keeper: bool,
+
+        /// Open the data directory even if a newer ClickHouse version wrote to it
+        #[arg(long)]
+        allow_downgrade: bool,
+
         /// Arguments to pass to clickhouse-server
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
@@ -149,6 +387,8 @@ CONTEXT FOR AGENTS:
   Connects to a running clickhouse-server. Server must already be running via `chv run server`.
   Pass clickhouse-client args after -- (e.g., `chv run client -- --query 'SELECT 1'`).
   Common args: --host, --port, --query, --multiquery, --format.
+  With --host of a Cloud service saved by `chv cloud service reset-password --save` and no
+  --password, the saved password (and --secure) is added.
   Related: `chv run server` to start a server first.")]
     Client {
         /// Arguments to pass to clickhouse-client
@@ -171,6 +411,280 @@ CONTEXT FOR AGENTS:
     },
 }
 
+#[derive(Subcommand)]
+pub enum DataCommands {
+    /// Copy a version's data directory to another version
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Copies .clickhouse/{from}/ to .clickhouse/{to}/ so tables survive a version switch.
+  Takes exact installed version strings (see `chv list`). --to defaults to the default version.
+  --hardlink shares immutable MergeTree part files instead of copying them (saves disk and time).
+  --verify starts the new version against the copy on free ports and reports tables that failed to load.
+  Refuses to run while a server is using either directory. --force replaces existing data in {to}.
+  Refuses data written by a newer version than {to} unless --allow-downgrade is given.
+  Typical: `chv use 25.3 && chv data migrate --from 24.8.1.1 --verify`.
+  Related: `chv use <version>`, `chv list`.")]
+    Migrate {
+        /// Version whose data should be copied
+        #[arg(long)]
+        from: String,
+
+        /// Version to copy the data to (default: current default version)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Hardlink immutable data part files instead of copying them
+        #[arg(long)]
+        hardlink: bool,
+
+        /// Start the target version against the copy and check that all tables attach
+        #[arg(long)]
+        verify: bool,
+
+        /// Replace existing data in the target version's directory
+        #[arg(long)]
+        force: bool,
+
+        /// Allow copying data written by a newer version than --to
+        #[arg(long)]
+        allow_downgrade: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotCommands {
+    /// Save the current version's data as a snapshot
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Copies .clickhouse/{version}/ into .clickhouse/snapshots/{name}/. Data parts are hardlinked.
+  Stop the server first for a consistent snapshot. --force overwrites an existing snapshot.
+  Related: `chv snapshot list`, `chv snapshot restore <name>`.")]
+    Save {
+        /// Snapshot name (letters, digits, '-', '_', '.')
+        name: String,
+
+        /// Overwrite an existing snapshot with the same name
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Replace the current version's data with a snapshot
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Replaces .clickhouse/{version}/ with the snapshot's data. Current data is discarded.
+  Fails if the server is running or the snapshot was taken with a different version.
+  Related: `chv snapshot list` to see snapshot versions, `chv use <version>` to switch.")]
+    Restore {
+        /// Snapshot name
+        name: String,
+    },
+
+    /// List snapshots
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Lists snapshots with their version, size and creation time (UTC), newest first.
+  Related: `chv snapshot restore <name>`.")]
+    List,
+
+    /// Delete a snapshot
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Permanently deletes .clickhouse/snapshots/{name}/.
+  Related: `chv snapshot list`.")]
+    Rm {
+        /// Snapshot name
+        name: String,
+    },
+}
+
+/// Connection options for commands that talk to a ClickHouse server
+#[derive(Args, Clone)]
+pub struct ConnectionArgs {
+    /// Server host
+    #[arg(long, default_value = "localhost")]
+    pub host: String,
+
+    /// Server native protocol port
+    #[arg(long, default_value_t = 9000)]
+    pub port: u16,
+
+    /// User name
+    #[arg(long, default_value = "default")]
+    pub user: String,
+
+    /// Password
+    #[arg(long)]
+    pub password: Option<String>,
+
+    /// Database for unqualified names
+    #[arg(long)]
+    pub database: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub enum SchemaCommands {
+    /// Create the project's tables and materialized views on a server
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Executes every statement in clickhouse/{tables,dictionaries,views,materialized_views}/*.sql.
+  Files are ordered by dependency: tables before views that select from them, target tables
+  before materialized views that write into them (TO db.table).
+  Stops at the first failing statement and reports file:line with ClickHouse's error.
+  Use CREATE ... IF NOT EXISTS in files to make apply re-runnable. --dry-run prints the order only.
+  Related: `chv run server` to start the local server first.")]
+    Apply {
+        #[command(flatten)]
+        conn: ConnectionArgs,
+
+        /// Print the order files would be applied in without executing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Show drift between the project's definitions and a server
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Compares CREATE statements in the clickhouse/ definition directories (normalised
+  through `clickhouse format`) with create_table_query from system.tables on the server.
+  Prints objects only in files (+), only on the server (-) and changed (~) with column-level
+  differences (added, removed, retyped columns) and engine/query changes.
+  Compares the databases the files refer to plus the connection's default database.
+  Exits non-zero when there is any drift, so it can gate CI.
+  Related: `chv schema apply` to create missing objects.")]
+    Diff {
+        #[command(flatten)]
+        conn: ConnectionArgs,
+    },
+
+    /// Write a server's tables, views and dictionaries into the project scaffold
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Reads SHOW CREATE for every table, materialized view, view and dictionary and writes one
+  file per object, formatted with `clickhouse format` and made re-runnable (IF NOT EXISTS):
+    tables/ · materialized_views/ · views/ · dictionaries/
+  Files are named {name}.sql for the default database and {database}.{name}.sql otherwise.
+  --database limits the dump to one database (default: all non-system databases).
+  Existing files that differ from the server are skipped unless --force is given.
+  Databases themselves are not dumped; create them before `chv schema apply` on a fresh server.
+  Related: `chv schema diff` to compare afterwards, `chv schema apply` to recreate locally.")]
+    Dump {
+        #[command(flatten)]
+        conn: ConnectionArgs,
+
+        /// Overwrite existing files that differ from the server
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Show the table → materialized view → table dependency graph
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Builds the graph from CREATE statements in the clickhouse/ definition directories, or from
+  system.tables on a server with --server (uses --host/--port etc.).
+  Edges: table → view/materialized view (reads via FROM/JOIN), materialized view → TO table (writes).
+  --format mermaid (default, paste into Markdown), dot (Graphviz: `| dot -Tsvg`), or json
+  ({nodes: [{name, kind, downstream}], edges: [{from, to, kind}]}).
+  --downstream <table> highlights that table and everything fed from it.
+  Related: `chv schema apply` uses the same dependencies to order files.")]
+    Graph {
+        /// Output format: mermaid, dot or json
+        #[arg(long, default_value = "mermaid")]
+        format: String,
+
+        /// Highlight this table and everything downstream of it
+        #[arg(long)]
+        downstream: Option<String>,
+
+        /// Read dependencies from a server instead of the project files
+        #[arg(long)]
+        server: bool,
+
+        #[command(flatten)]
+        conn: ConnectionArgs,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MigrateCommands {
+    /// Create a new pair of up/down migration files
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Creates clickhouse/migrations/{YYYYMMDDHHMMSS}_{name}.up.sql and .down.sql with header comments.
+  The name is lowercased with non-alphanumerics replaced by '_'. Does not connect to a server.
+  Related: `chv migrate up` to apply it.")]
+    New {
+        /// Short description, e.g. add_users_table
+        name: String,
+    },
+
+    /// Apply pending migrations in order
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Runs every pending *.up.sql in timestamp order and records it in chv_migrations.
+  --to {timestamp} stops after that migration. Stops at the first failing statement (file:line).
+  Fails before running anything if an applied migration's up file changed since it was applied.
+  Related: `chv migrate status` to see what is pending.")]
+    Up {
+        #[command(flatten)]
+        conn: ConnectionArgs,
+
+        /// Apply migrations up to and including this timestamp
+        #[arg(long)]
+        to: Option<String>,
+    },
+
+    /// Revert the most recently applied migrations
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Runs the *.down.sql of the latest applied migrations (newest first) and marks them unapplied.
+  --steps N reverts N migrations (default 1). Fails if a down file is missing or empty.
+  Related: `chv migrate status`.")]
+    Down {
+        #[command(flatten)]
+        conn: ConnectionArgs,
+
+        /// Number of migrations to revert
+        #[arg(long, default_value_t = 1)]
+        steps: usize,
+    },
+
+    /// Show applied, pending and changed migrations
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Lists each migration as applied, pending, changed (up file edited after apply) or
+  missing (recorded on the server but no file on disk).
+  Related: `chv migrate up`, `chv migrate down`.")]
+    Status {
+        #[command(flatten)]
+        conn: ConnectionArgs,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum KeeperCommands {
+    /// Start Keeper in the background
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Starts `clickhouse keeper` from the default version in the background.
+  Writes its config, logs and coordination data to .clickhouse/keeper/. Fails if already running.
+  Related: `chv keeper status` to verify, `chv run server --keeper` to connect a server.")]
+    Start,
+
+    /// Stop the running Keeper
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Stops the Keeper started by `chv keeper start`. Coordination data is kept in .clickhouse/keeper/.
+  Related: `chv keeper start` to start it again.")]
+    Stop,
+
+    /// Show whether Keeper is running
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Shows whether the local Keeper is running, with its PID, port and data directory.
+  Related: `chv keeper start`, `chv keeper stop`.")]
+    Status,
+}
+
 #[derive(Args)]
 pub struct CloudArgs {
     /// API key (or set CLICKHOUSE_CLOUD_API_KEY)
@@ -181,20 +695,37 @@ pub struct CloudArgs {
     #[arg(long, global = true)]
     pub api_secret: Option<String>,
 
+    /// API base URL (or set CLICKHOUSE_CLOUD_API_URL)
+    #[arg(long, global = true)]
+    pub api_url: Option<String>,
+
     /// Output as JSON
     #[arg(long, global = true)]
     pub json: bool,
 
+    /// Retries for failed API calls (429, 5xx, timeouts); creates are only retried if not processed
+    #[arg(long, global = true, default_value_t = 3)]
+    pub max_retries: u32,
+
+    /// Seconds to wait for a connection to the API
+    #[arg(long, global = true, default_value_t = 10)]
+    pub connect_timeout: u64,
+
+    /// Seconds to wait for each API request to complete
+    #[arg(long, global = true, default_value_t = 60)]
+    pub request_timeout: u64,
+
     #[command(subcommand)]
     pub command: CloudCommands,
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum CloudCommands {
     /// Organization commands
     #[command(after_help = "\
 CONTEXT FOR AGENTS:
-  Manage ClickHouse Cloud organizations. Subcommands: list, get.
+  Manage ClickHouse Cloud organizations. Subcommands: list, get, use.
   Org IDs are needed for most service and backup operations.
   Start with `chv cloud org list` to discover available org IDs.
   Related: `chv cloud service list` (uses org ID).")]
@@ -206,10 +737,14 @@ CONTEXT FOR AGENTS:
     /// Service commands
     #[command(after_help = "\
 CONTEXT FOR AGENTS:
-  Manage ClickHouse Cloud services. Subcommands: list, get, create, delete, start, stop.
-  Most commands need a service ID — get it from `chv cloud service list`.
-  Org ID is auto-detected if you have only one org; otherwise pass --org-id.
-  Add --json for machine-readable output. All write operations are immediate.
+  Manage ClickHouse Cloud services. Subcommands: list, get, create, update, scale, ip, reset-password,
+  delete, start, stop.
+  Most commands need a service — pass its ID, a unique ID prefix (like a git short hash),
+  or its name; see `chv cloud service list`.
+  Org ID comes from --org-id, CLICKHOUSE_CLOUD_ORG_ID or `chv cloud org use`; with a single
+  org it is auto-detected, with several and no choice the command fails.
+  Add --json for machine-readable output. Write operations return once the API accepts them;
+  create, scale, delete, start and stop take --wait to poll until the change has taken effect.
   Related: `chv cloud org list` for org IDs, `chv cloud backup list` for service backups.")]
     Service {
         #[command(subcommand)]
@@ -222,14 +757,18 @@ CONTEXT FOR AGENTS:
   Prompts for API key and secret, saves to .clickhouse/credentials.json (project-local).
   Credentials are auto-loaded by subsequent cloud commands.
   Fallback order: --api-key/--api-secret flags > credentials file > env vars.
+  `chv cloud auth status` shows which credentials and organization are in use.
   Related: `chv cloud org list` to verify credentials work.")]
-    Auth,
+    Auth {
+        #[command(subcommand)]
+        command: Option<AuthCommands>,
+    },
 
     /// Backup commands
     #[command(after_help = "\
 CONTEXT FOR AGENTS:
   Manage ClickHouse Cloud backups. Subcommands: list, get.
-  Requires a service ID — get it from `chv cloud service list`.
+  Requires a service ID, unique ID prefix or name — see `chv cloud service list`.
   Backup IDs from `backup list` can be used with `service create --backup-id` to restore.
   Related: `chv cloud service list` for service IDs.")]
     Backup {
@@ -260,14 +799,46 @@ CONTEXT FOR AGENTS:
         /// Organization ID
         org_id: String,
     },
+
+    /// Choose the organization used when --org-id is not given
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Saves the organization (by ID or name) to .clickhouse/cloud.json, or with --global to
+  ~/.clickhouse/cloud.json. Commands without --org-id then use it.
+  Order: --org-id > CLICKHOUSE_CLOUD_ORG_ID > project choice > global choice > the only org.
+  With several orgs and no choice, commands fail instead of guessing.
+  Related: `chv cloud auth status` shows the organization in use and where it comes from.")]
+    Use {
+        /// Organization ID or name
+        org: String,
+
+        /// Save for all projects instead of only this one
+        #[arg(long)]
+        global: bool,
+    },
 }
 
 #[derive(Subcommand)]
+pub enum AuthCommands {
+    /// Show the credentials, API URL and organization in use
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Verifies the credentials by listing organizations, then prints the API URL, where the
+  credentials come from, and the organization in use with its source
+  (CLICKHOUSE_CLOUD_ORG_ID, .clickhouse/cloud.json, ~/.clickhouse/cloud.json, or the only org).
+  Add --json for machine-readable output.
+  Related: `chv cloud org use <id|name>` to choose an organization.")]
+    Status,
+}
+
+#[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum ServiceCommands {
     /// List all services
     #[command(after_help = "\
 CONTEXT FOR AGENTS:
-  Lists all services in the organization. Org ID is auto-detected if only one org exists.
+  Lists all services in the organization. Org ID is auto-detected if only one org exists
+  (or chosen with `chv cloud org use`).
   Returns service IDs needed by get, delete, start, stop, and backup commands.
   Add --json for machine-readable output.
   Related: `chv cloud service get <id>` for full details.")]
@@ -281,11 +852,11 @@ CONTEXT FOR AGENTS:
     #[command(after_help = "\
 CONTEXT FOR AGENTS:
   Returns full service details: status, endpoints, scaling config, IP access list.
-  Get the service ID from `chv cloud service list`.
+  Accepts the service ID, a unique ID prefix, or the service name (see `chv cloud service list`).
   Add --json for machine-readable output.
   Related: `chv cloud service start/stop <id>` to change state.")]
     Get {
-        /// Service ID
+        /// Service ID, unique ID prefix, or name
         service_id: String,
 
         /// Organization ID (auto-detected if not specified)
@@ -300,6 +871,7 @@ CONTEXT FOR AGENTS:
   Returns the new service ID and initial password — save these.
   Typical: `chv cloud service create --name my-svc`.
   Defaults: provider=aws, region=us-east-1. Add --json for machine-readable output.
+  --wait polls until the service is running (up to --timeout seconds, default 1800).
   Related: `chv cloud service get <id>` to check status after creation.")]
     Create {
         /// Service name (required)
@@ -381,55 +953,318 @@ CONTEXT FOR AGENTS:
         /// Organization ID (auto-detected if not specified)
         #[arg(long)]
         org_id: Option<String>,
+
+        /// Wait until the service is running
+        #[arg(long)]
+        wait: bool,
+
+        /// Seconds to wait with --wait before giving up
+        #[arg(long, default_value_t = 1800, requires = "wait")]
+        timeout: u64,
+    },
+
+    /// Update a service's settings
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Changes an existing service and prints a before/after list of the fields that changed.
+  --name, --release-channel, --add-ip/--remove-ip and --add-tag/--remove-tag use the service PATCH;
+  --idle-scaling and --idle-timeout-minutes use the replica scaling PATCH.
+  --add-ip/--remove-ip/--add-tag/--remove-tag can be repeated. Tags are KEY=VALUE; remove by KEY.
+  Add --json to get {before, after} service objects.
+  Related: `chv cloud service get <id>` to see current settings.")]
+    Update {
+        /// Service ID, unique ID prefix, or name
+        service_id: String,
+
+        /// Organization ID (auto-detected if not specified)
+        #[arg(long)]
+        org_id: Option<String>,
+
+        /// New service name
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Release channel: slow, default, fast
+        #[arg(long)]
+        release_channel: Option<String>,
+
+        /// Allow scale to zero when idle
+        #[arg(long)]
+        idle_scaling: Option<bool>,
+
+        /// Minimum idle timeout in minutes (>= 5)
+        #[arg(long)]
+        idle_timeout_minutes: Option<u32>,
+
+        /// IP address or CIDR to allow. Can be specified multiple times
+        #[arg(long)]
+        add_ip: Vec<String>,
+
+        /// IP address or CIDR to remove from the allow list. Can be specified multiple times
+        #[arg(long)]
+        remove_ip: Vec<String>,
+
+        /// Tag to add as KEY=VALUE. Can be specified multiple times
+        #[arg(long)]
+        add_tag: Vec<String>,
+
+        /// Key of a tag to remove. Can be specified multiple times
+        #[arg(long)]
+        remove_tag: Vec<String>,
+    },
+
+    /// Change a service's replica memory and replica count
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Calls the replica scaling endpoint. Pass any of --min-replica-memory-gb, --max-replica-memory-gb
+  (8-356, multiple of 4, min <= max) and --num-replicas (1-20); values are checked before sending.
+  Prints the fields that changed; --json gives {before, after} service objects.
+  --wait polls until the new settings are reported and the service is not transitioning
+  (up to --timeout seconds, default 1800).
+  Related: `chv cloud service update` for idle scaling and other settings.")]
+    Scale {
+        /// Service ID, unique ID prefix, or name
+        service_id: String,
+
+        /// Organization ID (auto-detected if not specified)
+        #[arg(long)]
+        org_id: Option<String>,
+
+        /// Minimum memory per replica in GB (8-356, multiple of 4)
+        #[arg(long)]
+        min_replica_memory_gb: Option<u32>,
+
+        /// Maximum memory per replica in GB (8-356, multiple of 4)
+        #[arg(long)]
+        max_replica_memory_gb: Option<u32>,
+
+        /// Number of replicas (1-20)
+        #[arg(long)]
+        num_replicas: Option<u32>,
+
+        /// Wait until the new configuration is active
+        #[arg(long)]
+        wait: bool,
+
+        /// Seconds to wait with --wait before giving up
+        #[arg(long, default_value_t = 1800, requires = "wait")]
+        timeout: u64,
+    },
+
+    /// Manage a service's IP access list
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Subcommands: list, add, remove, set. Each takes the service ID, a unique ID prefix, or name.
+  Entries are IP addresses or CIDR blocks (IPv4 or IPv6), validated before any API call.
+  Services created without --ip-allow accept 0.0.0.0/0 (any address); tighten with
+  `chv cloud service ip set <id> --add-my-ip` plus any other CIDRs you need.
+  Related: `chv cloud service get <id>` shows the list with the other settings.")]
+    Ip {
+        #[command(subcommand)]
+        command: IpCommands,
+    },
+
+    /// Generate a new password for a service's default user
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Calls the service password endpoint, which generates a new password for the default user
+  and invalidates the old one. The password is printed once.
+  --save also stores it (with the service's host and port) in .clickhouse/secrets.json,
+  readable only by you. Then `chv run client -- --host <host>` and commands taking
+  --host/--port (use --port 9440) connect without --password.
+  Related: `chv cloud service get <id>` for the service host.")]
+    ResetPassword {
+        /// Service ID, unique ID prefix, or name
+        service_id: String,
+
+        /// Organization ID (auto-detected if not specified)
+        #[arg(long)]
+        org_id: Option<String>,
+
+        /// Store the password in .clickhouse/secrets.json for client connections
+        #[arg(long)]
+        save: bool,
     },
 
     /// Delete a service
     #[command(after_help = "\
 CONTEXT FOR AGENTS:
   Permanently deletes a ClickHouse Cloud service. This action is irreversible.
-  Takes a service ID — get it from `chv cloud service list`.
+  Takes a service ID, unique ID prefix or name — see `chv cloud service list`.
   Add --json for machine-readable output.
+  --wait polls until the service is gone (up to --timeout seconds, default 1800).
   Related: `chv cloud service stop <id>` to idle instead of delete.")]
     Delete {
-        /// Service ID
+        /// Service ID, unique ID prefix, or name
         service_id: String,
 
         /// Organization ID (auto-detected if not specified)
         #[arg(long)]
         org_id: Option<String>,
+
+        /// Wait until the service is gone
+        #[arg(long)]
+        wait: bool,
+
+        /// Seconds to wait with --wait before giving up
+        #[arg(long, default_value_t = 1800, requires = "wait")]
+        timeout: u64,
     },
 
     /// Start a service
     #[command(after_help = "\
 CONTEXT FOR AGENTS:
   Resumes a stopped/idled ClickHouse Cloud service.
-  Takes a service ID — get it from `chv cloud service list`.
+  Takes a service ID, unique ID prefix or name — see `chv cloud service list`.
   Add --json for machine-readable output.
+  --wait polls until the service is running (up to --timeout seconds, default 1800);
+  fails if it lands in a failed/degraded state.
   Related: `chv cloud service get <id>` to check status, `chv cloud service stop <id>` to idle.")]
     Start {
-        /// Service ID
+        /// Service ID, unique ID prefix, or name
         service_id: String,
 
         /// Organization ID (auto-detected if not specified)
         #[arg(long)]
         org_id: Option<String>,
+
+        /// Wait until the service is running
+        #[arg(long)]
+        wait: bool,
+
+        /// Seconds to wait with --wait before giving up
+        #[arg(long, default_value_t = 1800, requires = "wait")]
+        timeout: u64,
     },
 
     /// Stop a service
     #[command(after_help = "\
 CONTEXT FOR AGENTS:
   Idles a ClickHouse Cloud service, stopping billing for compute.
-  Data is preserved. Takes a service ID — get it from `chv cloud service list`.
+  Data is preserved. Takes a service ID, unique ID prefix or name — see `chv cloud service list`.
   Add --json for machine-readable output.
+  --wait polls until the service is stopped (up to --timeout seconds, default 1800).
   Related: `chv cloud service start <id>` to resume, `chv cloud service delete <id>` to remove.")]
     Stop {
-        /// Service ID
+        /// Service ID, unique ID prefix, or name
+        service_id: String,
+
+        /// Organization ID (auto-detected if not specified)
+        #[arg(long)]
+        org_id: Option<String>,
+
+        /// Wait until the service is stopped
+        #[arg(long)]
+        wait: bool,
+
+        /// Seconds to wait with --wait before giving up
+        #[arg(long, default_value_t = 1800, requires = "wait")]
+        timeout: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum IpCommands {
+    /// List the IP access entries of a service
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Prints each allowed source (IP or CIDR) with its description.
+  Warns on stderr when 0.0.0.0/0 or ::/0 lets any address connect.
+  Add --json for the entries as a JSON array.
+  Related: `chv cloud service ip set <id> --add-my-ip` to restrict access.")]
+    List {
+        /// Service ID, unique ID prefix, or name
+        service_id: String,
+
+        /// Organization ID (auto-detected if not specified)
+        #[arg(long)]
+        org_id: Option<String>,
+    },
+
+    /// Allow more IP addresses or CIDR blocks
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Adds entries to the allow list; sources already present are skipped.
+  --description applies to every entry added by this command.
+  --add-my-ip adds your public address (as /32 or /128), looked up through --my-ip-url,
+  CLICKHOUSE_CLOUD_MY_IP_URL, or https://api.ipify.org; the URL must answer with the bare address.
+  Example: `chv cloud service ip add analytics 10.0.0.0/8 --description office`
+  Add --json for {\"before\", \"after\"} service objects.")]
+    Add {
+        /// Service ID, unique ID prefix, or name
+        service_id: String,
+
+        /// IP addresses or CIDR blocks to allow (e.g., 203.0.113.0/24)
+        sources: Vec<String>,
+
+        /// Organization ID (auto-detected if not specified)
+        #[arg(long)]
+        org_id: Option<String>,
+
+        /// Description stored with the new entries
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Also allow your current public IP address
+        #[arg(long)]
+        add_my_ip: bool,
+
+        /// URL answering with your public IP address as plain text (used with --add-my-ip)
+        #[arg(long, requires = "add_my_ip")]
+        my_ip_url: Option<String>,
+    },
+
+    /// Remove IP addresses or CIDR blocks from the allow list
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Removes the listed sources; a bare IP matches its /32 (or /128) entry.
+  Fails without changes if a source is not in the list.
+  Removing every entry blocks all connections to the service.
+  Related: `chv cloud service ip list <id>` for current entries.")]
+    Remove {
+        /// Service ID, unique ID prefix, or name
         service_id: String,
 
+        /// IP addresses or CIDR blocks to remove
+        #[arg(required = true)]
+        sources: Vec<String>,
+
         /// Organization ID (auto-detected if not specified)
         #[arg(long)]
         org_id: Option<String>,
     },
+
+    /// Replace the whole allow list
+    #[command(after_help = "\
+CONTEXT FOR AGENTS:
+  Makes the allow list exactly the given sources (plus your public IP with --add-my-ip);
+  every other entry, including 0.0.0.0/0, is removed. At least one entry is required.
+  --description applies to every given entry; without it, entries already in the list keep
+  their current description.
+  Example: `chv cloud service ip set analytics 10.0.0.0/8 --add-my-ip`")]
+    Set {
+        /// Service ID, unique ID prefix, or name
+        service_id: String,
+
+        /// IP addresses or CIDR blocks to allow
+        sources: Vec<String>,
+
+        /// Organization ID (auto-detected if not specified)
+        #[arg(long)]
+        org_id: Option<String>,
+
+        /// Description stored with the entries
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Also allow your current public IP address
+        #[arg(long)]
+        add_my_ip: bool,
+
+        /// URL answering with your public IP address as plain text (used with --add-my-ip)
+        #[arg(long, requires = "add_my_ip")]
+        my_ip_url: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -437,12 +1272,12 @@ pub enum BackupCommands {
     /// List backups for a service
     #[command(after_help = "\
 CONTEXT FOR AGENTS:
-  Lists all backups for a given service. Requires a service ID from `chv cloud service list`.
+  Lists all backups for a given service (ID, unique ID prefix or name from `chv cloud service list`).
   Returns backup IDs that can be used with `chv cloud service create --backup-id` to restore.
   Add --json for machine-readable output.
   Related: `chv cloud backup get` for details on a specific backup.")]
     List {
-        /// Service ID
+        /// Service ID, unique ID prefix, or name
         service_id: String,
 
         /// Organization ID (auto-detected if not specified)
@@ -458,7 +1293,7 @@ CONTEXT FOR AGENTS:
   Add --json for machine-readable output.
   Related: `chv cloud service create --backup-id <id>` to restore from this backup.")]
     Get {
-        /// Service ID
+        /// Service ID, unique ID prefix, or name
         service_id: String,
 
         /// Backup ID
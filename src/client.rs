@@ -0,0 +1,152 @@
+use crate::cli::ConnectionArgs;
+use crate::cloud::secrets;
+use crate::error::{Error, Result};
+use crate::paths;
+use crate::sql::{self, Statement};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Formats SQL with the version's `clickhouse format` tool, passing `args` (e.g. --oneline)
+pub fn format_sql(version: &str, sql: &str, args: &[&str]) -> Result<String> {
+    let binary = paths::binary_path(version)?;
+    if !binary.exists() {
+        return Err(Error::VersionNotFound(version.to_string()));
+    }
+
+    let mut child = Command::new(binary)
+        .arg("format")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(sql.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(Error::Query(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Runs queries against a ClickHouse server through the installed clickhouse-client
+pub struct Client {
+    binary: PathBuf,
+    args: Vec<String>,
+    /// Passed through CLICKHOUSE_PASSWORD so it doesn't show up in the process list
+    password: Option<String>,
+    endpoint: String,
+}
+
+impl Client {
+    /// Creates a client using the given version's binary and connection options
+    pub fn new(version: &str, conn: &ConnectionArgs) -> Result<Self> {
+        let binary = paths::binary_path(version)?;
+        if !binary.exists() {
+            return Err(Error::VersionNotFound(version.to_string()));
+        }
+
+        let mut args = vec![
+            format!("--host={}", conn.host),
+            format!("--port={}", conn.port),
+            format!("--user={}", conn.user),
+        ];
+        // Fall back to a password saved by `chv cloud service reset-password --save`
+        let saved = match &conn.password {
+            Some(_) => None,
+            None => secrets::find(&conn.host, &conn.user),
+        };
+        if saved.is_some() && conn.port == secrets::SECURE_NATIVE_PORT {
+            args.push("--secure".to_string());
+        }
+        if let Some(database) = &conn.database {
+            args.push(format!("--database={}", database));
+        }
+
+        Ok(Self {
+            binary,
+            args,
+            password: conn.password.clone().or(saved.map(|s| s.password)),
+            endpoint: format!("{}:{}", conn.host, conn.port),
+        })
+    }
+
+    /// Returns host:port for messages
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Returns a clickhouse-client command with the connection arguments set,
+    /// for callers that hand the terminal over to the client
+    pub fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.binary);
+        cmd.arg("client").args(&self.args);
+        if let Some(password) = &self.password {
+            cmd.env("CLICKHOUSE_PASSWORD", password);
+        }
+        cmd
+    }
+
+    /// Runs a single statement and returns its output in TSV format
+    pub fn query(&self, sql: &str) -> Result<String> {
+        self.query_with(sql, &[])
+    }
+
+    /// Runs a single statement with extra clickhouse-client arguments (e.g. --format, --param_x)
+    pub fn query_with(&self, sql: &str, extra: &[String]) -> Result<String> {
+        let mut cmd = self.command();
+        if !extra.iter().any(|a| a.starts_with("--format")) {
+            cmd.arg("--format=TSV");
+        }
+        cmd.args(extra).arg("--query").arg(sql);
+
+        let output = cmd.output()?;
+        if !output.status.success() {
+            return Err(Error::Query(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Streams a data file into `INSERT INTO {table} FORMAT {format}` through stdin
+    pub fn insert_file(&self, table: &str, format: &str, path: &Path) -> Result<()> {
+        let output = self
+            .command()
+            .arg("--query")
+            .arg(format!("INSERT INTO {} FORMAT {}", table, format))
+            .stdin(File::open(path)?)
+            .output()?;
+        if !output.status.success() {
+            return Err(Error::Query(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Runs the statements of a file in order. On failure the error names the file
+    /// and line of the failing statement, followed by that line's text.
+    pub fn execute_file(&self, rel: &str, text: &str, statements: &[Statement]) -> Result<()> {
+        for stmt in statements {
+            if let Err(e) = self.query(&stmt.sql) {
+                let message = match e {
+                    Error::Query(m) => m,
+                    other => other.to_string(),
+                };
+                let line = sql::error_line(stmt, &message);
+                let context = text.lines().nth(line - 1).unwrap_or("").trim();
+                return Err(Error::Query(format!(
+                    "{}:{}: {}\n  {} | {}",
+                    rel, line, message, line, context
+                )));
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,18 +1,51 @@
 use crate::cloud::types::*;
 use base64::Engine;
-use reqwest::Client;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, Method, StatusCode};
 use std::env;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-const BASE_URL: &str = "https://api.clickhouse.cloud/v1";
+const DEFAULT_BASE_URL: &str = "https://api.clickhouse.cloud/v1";
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct CloudError {
     pub message: String,
+    /// HTTP status of the failed response, if the API answered
+    pub status: Option<u16>,
+    /// Error code from the API error body, e.g. NOT_FOUND
+    pub code: Option<String>,
+    /// Request id to quote when contacting support
+    pub request_id: Option<String>,
+    /// The API could not be reached or did not answer in time
+    pub network: bool,
+}
+
+impl CloudError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            ..Default::default()
+        }
+    }
 }
 
 impl std::fmt::Display for CloudError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.message)
+        write!(f, "{}", self.message)?;
+        let mut details = Vec::new();
+        if let Some(status) = self.status {
+            details.push(format!("HTTP {}", status));
+        }
+        if let Some(code) = &self.code {
+            details.push(format!("code {}", code));
+        }
+        if let Some(request_id) = &self.request_id {
+            details.push(format!("request id {}", request_id));
+        }
+        if !details.is_empty() {
+            write!(f, " ({})", details.join(", "))?;
+        }
+        Ok(())
     }
 }
 
@@ -20,31 +53,81 @@ impl std::error::Error for CloudError {}
 
 pub type Result<T> = std::result::Result<T, CloudError>;
 
+/// Connection and retry settings for the Cloud API
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// API base URL; falls back to the credentials file, CLICKHOUSE_CLOUD_API_URL, then the public API
+    pub base_url: Option<String>,
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    /// Extra attempts after the first for retryable failures
+    pub max_retries: u32,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(60),
+            max_retries: 3,
+        }
+    }
+}
+
+/// First backoff delay; doubles with each retry
+const BASE_DELAY: Duration = Duration::from_millis(500);
+/// Upper bound for a single backoff or Retry-After delay
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
 pub struct CloudClient {
     client: Client,
+    base_url: String,
+    /// Where the API key came from, for `chv cloud auth status`
+    credentials_source: String,
+    key_prefix: String,
     auth_header: String,
+    max_retries: u32,
 }
 
 impl CloudClient {
-    pub fn new(api_key: Option<&str>, api_secret: Option<&str>) -> Result<Self> {
+    pub fn new(
+        api_key: Option<&str>,
+        api_secret: Option<&str>,
+        options: ClientOptions,
+    ) -> Result<Self> {
         let file_creds = crate::cloud::credentials::load_credentials();
 
         let key = api_key
             .map(String::from)
             .or_else(|| file_creds.as_ref().map(|c| c.api_key.clone()))
             .or_else(|| env::var("CLICKHOUSE_CLOUD_API_KEY").ok())
-            .ok_or_else(|| CloudError {
-                message: "API key required. Run `chv cloud auth`, set CLICKHOUSE_CLOUD_API_KEY, or use --api-key".into(),
-            })?;
+            .ok_or_else(|| CloudError::new("API key required. Run `chv cloud auth`, set CLICKHOUSE_CLOUD_API_KEY, or use --api-key"))?;
 
         let secret = api_secret
             .map(String::from)
             .or_else(|| file_creds.as_ref().map(|c| c.api_secret.clone()))
             .or_else(|| env::var("CLICKHOUSE_CLOUD_API_SECRET").ok())
-            .ok_or_else(|| CloudError {
-                message: "API secret required. Run `chv cloud auth`, set CLICKHOUSE_CLOUD_API_SECRET, or use --api-secret"
-                    .into(),
-            })?;
+            .ok_or_else(|| CloudError::new("API secret required. Run `chv cloud auth`, set CLICKHOUSE_CLOUD_API_SECRET, or use --api-secret"))?;
+
+        let base_url = options
+            .base_url
+            .or_else(|| file_creds.as_ref().and_then(|c| c.api_url.clone()))
+            .or_else(|| env::var("CLICKHOUSE_CLOUD_API_URL").ok())
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+            .trim_end_matches('/')
+            .to_string();
+
+        let credentials_source = if api_key.is_some() {
+            "--api-key/--api-secret".to_string()
+        } else if file_creds.is_some() {
+            crate::cloud::credentials::credentials_path()
+                .display()
+                .to_string()
+        } else {
+            "CLICKHOUSE_CLOUD_API_KEY/CLICKHOUSE_CLOUD_API_SECRET".to_string()
+        };
+        let key_prefix: String = key.chars().take(4).collect();
 
         let credentials = format!("{}:{}", key, secret);
         let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
@@ -52,102 +135,119 @@ impl CloudClient {
 
         let client = Client::builder()
             .user_agent("chv-cli")
+            .connect_timeout(options.connect_timeout)
+            .timeout(options.request_timeout)
             .build()
-            .map_err(|e| CloudError {
-                message: format!("Failed to create HTTP client: {}", e),
-            })?;
+            .map_err(|e| CloudError::new(format!("Failed to create HTTP client: {}", e)))?;
 
         Ok(Self {
             client,
+            base_url,
+            credentials_source,
+            key_prefix,
             auth_header,
+            max_retries: options.max_retries,
         })
     }
 
-    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
-        let url = format!("{}{}", BASE_URL, path);
-        let response = self
-            .client
-            .get(&url)
-            .header("Authorization", &self.auth_header)
-            .send()
-            .await
-            .map_err(|e| CloudError {
-                message: format!("Request failed: {}", e),
-            })?;
-
-        let status = response.status();
-        let body = response.text().await.map_err(|e| CloudError {
-            message: format!("Failed to read response: {}", e),
-        })?;
-
-        if !status.is_success() {
-            // Try to parse error response
-            if let Ok(api_resp) = serde_json::from_str::<ApiResponse<()>>(&body) {
-                if let Some(err) = api_resp.error {
-                    return Err(CloudError {
-                        message: err.message,
-                    });
-                }
+    /// Sends a request and returns the response body of a successful call.
+    ///
+    /// Idempotent calls are retried on 429, 5xx, timeouts and connection failures.
+    /// Non-idempotent calls are only retried when the request provably was not
+    /// processed: a 429 or a failure to connect.
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+        idempotent: bool,
+    ) -> Result<String> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut attempt = 0;
+        loop {
+            let mut request = self
+                .client
+                .request(method.clone(), &url)
+                .header("Authorization", &self.auth_header);
+            if let Some(body) = body {
+                request = request.json(body);
             }
-            return Err(CloudError {
-                message: format!("API error ({}): {}", status, body),
-            });
-        }
 
-        let api_response: ApiResponse<T> =
-            serde_json::from_str(&body).map_err(|e| CloudError {
-                message: format!("Failed to parse response: {} - Body: {}", e, body),
-            })?;
+            let (retry_after, reason) = match request.send().await {
+                Ok(response) => {
+                    let status = response.status();
+                    let retry_after = response
+                        .headers()
+                        .get(RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(parse_retry_after);
+                    let request_id = response
+                        .headers()
+                        .get("x-request-id")
+                        .and_then(|v| v.to_str().ok())
+                        .map(String::from);
+                    let body = response
+                        .text()
+                        .await
+                        .map_err(|e| CloudError::new(format!("Failed to read response: {}", e)))?;
+                    if status.is_success() {
+                        return Ok(body);
+                    }
+                    let retryable = status == StatusCode::TOO_MANY_REQUESTS
+                        || (idempotent && status.is_server_error());
+                    if !retryable || attempt >= self.max_retries {
+                        return Err(api_error(status, request_id, &body));
+                    }
+                    (retry_after, status.to_string())
+                }
+                Err(e) => {
+                    let retryable = e.is_connect() || (idempotent && e.is_timeout());
+                    if !retryable || attempt >= self.max_retries {
+                        return Err(CloudError {
+                            network: e.is_connect() || e.is_timeout(),
+                            ..CloudError::new(format!("Request failed: {}", e))
+                        });
+                    }
+                    let reason = if e.is_timeout() {
+                        "timed out"
+                    } else {
+                        "connection failed"
+                    };
+                    (None, reason.to_string())
+                }
+            };
+            let delay = retry_after
+                .map(|d| d.min(MAX_DELAY))
+                .unwrap_or_else(|| backoff_delay(attempt, jitter()));
+            attempt += 1;
+            eprintln!(
+                "{} {}: {}, retrying in {:.1}s ({}/{})",
+                method,
+                path,
+                reason,
+                delay.as_secs_f64(),
+                attempt,
+                self.max_retries
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
 
-        api_response.result.ok_or_else(|| CloudError {
-            message: "Empty response from API".into(),
-        })
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let body = self.send(Method::GET, path, None, true).await?;
+        parse_result(&body)
     }
 
+    /// POST creates resources, so it is never repeated once the server may have acted on it
     async fn post<T: serde::de::DeserializeOwned, B: serde::Serialize>(
         &self,
         path: &str,
         body: &B,
     ) -> Result<T> {
-        let url = format!("{}{}", BASE_URL, path);
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", &self.auth_header)
-            .header("Content-Type", "application/json")
-            .json(body)
-            .send()
-            .await
-            .map_err(|e| CloudError {
-                message: format!("Request failed: {}", e),
-            })?;
-
-        let status = response.status();
-        let body_text = response.text().await.map_err(|e| CloudError {
-            message: format!("Failed to read response: {}", e),
-        })?;
-
-        if !status.is_success() {
-            if let Ok(api_resp) = serde_json::from_str::<ApiResponse<()>>(&body_text) {
-                if let Some(err) = api_resp.error {
-                    return Err(CloudError {
-                        message: err.message,
-                    });
-                }
-            }
-            return Err(CloudError {
-                message: format!("API error ({}): {}", status, body_text),
-            });
-        }
-
-        let api_response: ApiResponse<T> =
-            serde_json::from_str(&body_text).map_err(|e| CloudError {
-                message: format!("Failed to parse response: {} - Body: {}", e, body_text),
-            })?;
-
-        api_response.result.ok_or_else(|| CloudError {
-            message: "Empty response from API".into(),
-        })
+        let body = self
+            .send(Method::POST, path, Some(&to_json(body)?), false)
+            .await?;
+        parse_result(&body)
     }
 
     async fn patch<T: serde::de::DeserializeOwned, B: serde::Serialize>(
@@ -155,74 +255,14 @@ impl CloudClient {
         path: &str,
         body: &B,
     ) -> Result<T> {
-        let url = format!("{}{}", BASE_URL, path);
-        let response = self
-            .client
-            .patch(&url)
-            .header("Authorization", &self.auth_header)
-            .header("Content-Type", "application/json")
-            .json(body)
-            .send()
-            .await
-            .map_err(|e| CloudError {
-                message: format!("Request failed: {}", e),
-            })?;
-
-        let status = response.status();
-        let body_text = response.text().await.map_err(|e| CloudError {
-            message: format!("Failed to read response: {}", e),
-        })?;
-
-        if !status.is_success() {
-            if let Ok(api_resp) = serde_json::from_str::<ApiResponse<()>>(&body_text) {
-                if let Some(err) = api_resp.error {
-                    return Err(CloudError {
-                        message: err.message,
-                    });
-                }
-            }
-            return Err(CloudError {
-                message: format!("API error ({}): {}", status, body_text),
-            });
-        }
-
-        let api_response: ApiResponse<T> =
-            serde_json::from_str(&body_text).map_err(|e| CloudError {
-                message: format!("Failed to parse response: {} - Body: {}", e, body_text),
-            })?;
-
-        api_response.result.ok_or_else(|| CloudError {
-            message: "Empty response from API".into(),
-        })
+        let body = self
+            .send(Method::PATCH, path, Some(&to_json(body)?), true)
+            .await?;
+        parse_result(&body)
     }
 
     async fn delete(&self, path: &str) -> Result<()> {
-        let url = format!("{}{}", BASE_URL, path);
-        let response = self
-            .client
-            .delete(&url)
-            .header("Authorization", &self.auth_header)
-            .send()
-            .await
-            .map_err(|e| CloudError {
-                message: format!("Request failed: {}", e),
-            })?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let body = response.text().await.unwrap_or_default();
-            if let Ok(api_resp) = serde_json::from_str::<ApiResponse<()>>(&body) {
-                if let Some(err) = api_resp.error {
-                    return Err(CloudError {
-                        message: err.message,
-                    });
-                }
-            }
-            return Err(CloudError {
-                message: format!("API error ({}): {}", status, body),
-            });
-        }
-
+        self.send(Method::DELETE, path, None, true).await?;
         Ok(())
     }
 
@@ -282,6 +322,49 @@ impl CloudClient {
         .await
     }
 
+    pub async fn update_service(
+        &self,
+        org_id: &str,
+        service_id: &str,
+        request: &UpdateServiceRequest,
+    ) -> Result<Service> {
+        self.patch(
+            &format!("/organizations/{}/services/{}", org_id, service_id),
+            request,
+        )
+        .await
+    }
+
+    pub async fn update_replica_scaling(
+        &self,
+        org_id: &str,
+        service_id: &str,
+        request: &ReplicaScalingRequest,
+    ) -> Result<Service> {
+        self.patch(
+            &format!(
+                "/organizations/{}/services/{}/replicaScaling",
+                org_id, service_id
+            ),
+            request,
+        )
+        .await
+    }
+
+    /// Generates a new password for the service's default user. Retrying is safe: each call
+    /// replaces the password, and only the one returned last is valid.
+    pub async fn reset_service_password(
+        &self,
+        org_id: &str,
+        service_id: &str,
+    ) -> Result<PasswordChangeResponse> {
+        self.patch(
+            &format!("/organizations/{}/services/{}/password", org_id, service_id),
+            &PasswordChangeRequest::default(),
+        )
+        .await
+    }
+
     // Backup endpoints
     pub async fn list_backups(&self, org_id: &str, service_id: &str) -> Result<Vec<Backup>> {
         self.get(&format!(
@@ -304,13 +387,117 @@ impl CloudClient {
         .await
     }
 
-    // Helper to get the default organization
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Describes the credentials in use without revealing them
+    pub fn credentials_summary(&self) -> String {
+        format!("key {}… from {}", self.key_prefix, self.credentials_source)
+    }
+
+    /// Returns the organization to use when no --org-id is given: the one selected with
+    /// `chv cloud org use` or CLICKHOUSE_CLOUD_ORG_ID, else the only one this key can access
     pub async fn get_default_org_id(&self) -> Result<String> {
+        if let Some((org_id, _)) = crate::cloud::config::selected_org() {
+            return Ok(org_id);
+        }
         let orgs = self.list_organizations().await?;
-        orgs.first()
-            .map(|o| o.id.clone())
-            .ok_or_else(|| CloudError {
-                message: "No organization found for this API key".into(),
-            })
+        match orgs.as_slice() {
+            [] => Err(CloudError::new("No organization found for this API key")),
+            [org] => Ok(org.id.clone()),
+            _ => {
+                let names: Vec<String> = orgs
+                    .iter()
+                    .map(|o| format!("{} ({})", o.name, o.id))
+                    .collect();
+                Err(CloudError::new(format!(
+                    "This API key can access {} organizations: {}. Choose one with --org-id, \
+                     `chv cloud org use <id|name>`, or CLICKHOUSE_CLOUD_ORG_ID",
+                    orgs.len(),
+                    names.join(", ")
+                )))
+            }
+        }
+    }
+}
+
+fn to_json<B: serde::Serialize>(body: &B) -> Result<serde_json::Value> {
+    serde_json::to_value(body)
+        .map_err(|e| CloudError::new(format!("Failed to encode request: {}", e)))
+}
+
+/// Builds the error for a failed call, preferring the API's own message, code and request id
+fn api_error(status: StatusCode, request_id: Option<String>, body: &str) -> CloudError {
+    let mut error = CloudError {
+        status: Some(status.as_u16()),
+        request_id,
+        ..CloudError::new(format!("API error ({}): {}", status, body))
+    };
+    if let Ok(api_resp) = serde_json::from_str::<ApiResponse<()>>(body) {
+        if let Some(err) = api_resp.error {
+            error.message = err.message;
+            error.code = err.code;
+        }
+        if api_resp.request_id.is_some() {
+            error.request_id = api_resp.request_id;
+        }
+    }
+    error
+}
+
+fn parse_result<T: serde::de::DeserializeOwned>(body: &str) -> Result<T> {
+    let api_response: ApiResponse<T> = serde_json::from_str(body).map_err(|e| {
+        CloudError::new(format!("Failed to parse response: {} - Body: {}", e, body))
+    })?;
+
+    api_response
+        .result
+        .ok_or_else(|| CloudError::new("Empty response from API"))
+}
+
+/// Parses a Retry-After header given either as seconds or as an HTTP date
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    let wait = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Exponential backoff with full jitter: a random delay up to BASE_DELAY * 2^attempt,
+/// capped at MAX_DELAY. `jitter` is a fraction in [0, 1).
+fn backoff_delay(attempt: u32, jitter: f64) -> Duration {
+    let ceiling = BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_DELAY);
+    ceiling.mul_f64(jitter.clamp(0.0, 1.0))
+}
+
+/// A fraction in [0, 1) that varies between calls, good enough to spread out retries
+fn jitter() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    f64::from(nanos % 1_000_000) / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delays() {
+        assert_eq!(backoff_delay(0, 0.5), Duration::from_millis(250));
+        assert_eq!(backoff_delay(3, 1.0), Duration::from_secs(4));
+        assert_eq!(backoff_delay(20, 1.0), MAX_DELAY);
+        assert_eq!(parse_retry_after("7"), Some(Duration::from_secs(7)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
     }
 }
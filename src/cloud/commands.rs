@@ -1,7 +1,202 @@
-use crate::cloud::client::CloudClient;
+use crate::cloud::client::{CloudClient, CloudError};
+use crate::cloud::config::{self, CloudConfig};
 use crate::cloud::credentials::{self, Credentials};
+use crate::cloud::secrets::{self, ServiceSecret};
 use crate::cloud::types::*;
+use crate::units::format_bytes;
+use indicatif::{ProgressBar, ProgressStyle};
 use std::io::Write;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// How often `--wait` polls the service state
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// States a service does not recover from on its own
+const FAILED_STATES: &[&str] = &["failed", "degraded"];
+
+/// Shortest ID prefix accepted in place of a full service ID
+const MIN_ID_PREFIX: usize = 4;
+
+/// Shortest idle timeout the API accepts
+const MIN_IDLE_TIMEOUT_MINUTES: u32 = 5;
+
+/// Where `--add-my-ip` looks up the caller's public address, unless overridden by
+/// `--my-ip-url` or CLICKHOUSE_CLOUD_MY_IP_URL. Must answer with the bare address.
+const DEFAULT_MY_IP_URL: &str = "https://api.ipify.org";
+
+/// Entries that open a service to the whole internet
+const OPEN_SOURCES: &[&str] = &["0.0.0.0/0", "::/0"];
+
+/// Resolves a service reference to its ID. A full UUID is used as is; otherwise the
+/// reference is matched against service IDs, then names, then unique ID prefixes.
+async fn resolve_service_id(
+    client: &CloudClient,
+    org_id: &str,
+    reference: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if is_uuid(reference) {
+        return Ok(reference.to_string());
+    }
+    let services = client.list_services(org_id).await?;
+    Ok(match_service(&services, reference)?.id.clone())
+}
+
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+fn match_service<'a>(services: &'a [Service], reference: &str) -> Result<&'a Service, String> {
+    if let Some(svc) = services.iter().find(|s| s.id == reference) {
+        return Ok(svc);
+    }
+    let by_name: Vec<&Service> = services.iter().filter(|s| s.name == reference).collect();
+    let candidates = if !by_name.is_empty() {
+        by_name
+    } else if reference.len() >= MIN_ID_PREFIX {
+        services
+            .iter()
+            .filter(|s| s.id.starts_with(reference))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    match candidates.as_slice() {
+        [svc] => Ok(svc),
+        [] => Err(format!(
+            "No service with ID, name or ID prefix (at least {} characters) {}. \
+             Run `chv cloud service list`",
+            MIN_ID_PREFIX, reference
+        )),
+        _ => {
+            let names: Vec<String> = candidates
+                .iter()
+                .map(|s| format!("{} ({})", s.name, s.id))
+                .collect();
+            Err(format!(
+                "{} matches {} services: {}. Use a longer ID prefix or the full ID",
+                reference,
+                candidates.len(),
+                names.join(", ")
+            ))
+        }
+    }
+}
+
+/// Formats a duration as e.g. `2m 05s`
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// What `--wait` waits for
+enum WaitFor<'a> {
+    State(&'static str),
+    Deleted,
+    /// The requested replica settings are in place and the service is not transitioning
+    Scaled(&'a ReplicaScalingRequest),
+}
+
+impl WaitFor<'_> {
+    fn describe(&self) -> &'static str {
+        match self {
+            WaitFor::State(state) => state,
+            WaitFor::Deleted => "deleted",
+            WaitFor::Scaled(_) => "scaled",
+        }
+    }
+
+    fn reached(&self, svc: &Service) -> bool {
+        match self {
+            WaitFor::State(state) => svc.state == *state,
+            WaitFor::Deleted => svc.state == "terminated",
+            WaitFor::Scaled(request) => {
+                let matches =
+                    |wanted: Option<u32>, actual: Option<u32>| wanted.is_none() || wanted == actual;
+                matches(request.min_replica_memory_gb, svc.min_replica_memory_gb)
+                    && matches(request.max_replica_memory_gb, svc.max_replica_memory_gb)
+                    && matches(request.num_replicas, svc.num_replicas)
+                    && ["running", "stopped", "idle"].contains(&svc.state.as_str())
+            }
+        }
+    }
+}
+
+/// Polls a service until it reaches `target`, showing a spinner with the elapsed time.
+/// Returns the service in its final state, or None once it is gone.
+async fn wait_for_service(
+    client: &CloudClient,
+    org_id: &str,
+    service_id: &str,
+    target: WaitFor<'_>,
+    timeout: Duration,
+) -> Result<Option<Service>, Box<dyn std::error::Error>> {
+    let goal = target.describe();
+    let deleting = matches!(target, WaitFor::Deleted);
+    let started = Instant::now();
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner().template("{spinner:.green} [{elapsed_precise}] {msg}")?,
+    );
+    spinner.enable_steady_tick(Duration::from_millis(100));
+
+    let mut last_state;
+    loop {
+        match client.get_service(org_id, service_id).await {
+            Ok(svc) => {
+                if target.reached(&svc) {
+                    spinner.finish_and_clear();
+                    return Ok((!deleting).then_some(svc));
+                }
+                let gone = !deleting && svc.state.starts_with("terminat");
+                if gone || FAILED_STATES.contains(&svc.state.as_str()) {
+                    spinner.abandon();
+                    return Err(format!(
+                        "Service {} entered state {} while waiting for it to be {}",
+                        svc.name, svc.state, goal
+                    )
+                    .into());
+                }
+                spinner.set_message(format!(
+                    "Waiting for {} to be {} (state: {})",
+                    svc.name, goal, svc.state
+                ));
+                last_state = svc.state;
+            }
+            Err(e) if deleting && e.status == Some(404) => {
+                spinner.finish_and_clear();
+                return Ok(None);
+            }
+            Err(e) => {
+                spinner.abandon();
+                return Err(e.into());
+            }
+        }
+
+        let elapsed = started.elapsed();
+        if elapsed >= timeout {
+            spinner.abandon();
+            return Err(format!(
+                "Timed out after {} waiting for service {} to be {} (last state: {})",
+                format_elapsed(elapsed),
+                service_id,
+                goal,
+                last_state
+            )
+            .into());
+        }
+        tokio::time::sleep(POLL_INTERVAL.min(timeout - elapsed)).await;
+    }
+}
 
 pub async fn org_list(client: &CloudClient, json: bool) -> Result<(), Box<dyn std::error::Error>> {
     let orgs = client.list_organizations().await?;
@@ -13,14 +208,128 @@ pub async fn org_list(client: &CloudClient, json: bool) -> Result<(), Box<dyn st
             println!("No organizations found");
             return Ok(());
         }
+        let selected = config::selected_org().map(|(id, _)| id);
         println!("Organizations:");
         for org in orgs {
-            println!("  {} ({})", org.name, org.id);
+            let marker = if selected.as_ref() == Some(&org.id) {
+                " [in use]"
+            } else {
+                ""
+            };
+            println!("  {} ({}){}", org.name, org.id, marker);
         }
     }
     Ok(())
 }
 
+/// Persists the organization used when commands get no --org-id, for this project or,
+/// with `global`, for every project without its own choice
+pub async fn org_use(
+    client: &CloudClient,
+    id_or_name: &str,
+    global: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let orgs = client.list_organizations().await?;
+    let matches: Vec<&Organization> = match orgs.iter().find(|o| o.id == id_or_name) {
+        Some(org) => vec![org],
+        None => orgs
+            .iter()
+            .filter(|o| o.name.eq_ignore_ascii_case(id_or_name))
+            .collect(),
+    };
+    let org = match matches.as_slice() {
+        [org] => *org,
+        [] => {
+            return Err(format!(
+                "No organization with ID or name {}. Run `chv cloud org list`",
+                id_or_name
+            )
+            .into());
+        }
+        _ => {
+            return Err(format!(
+                "Several organizations are named {}; use the ID instead",
+                id_or_name
+            )
+            .into());
+        }
+    };
+
+    let path = if global {
+        config::global_config_path().ok_or("Could not determine home directory")?
+    } else {
+        config::project_config_path()
+    };
+    config::save_config(
+        &path,
+        &CloudConfig {
+            org_id: Some(org.id.clone()),
+        },
+    )?;
+    println!(
+        "Using organization {} ({}), saved to {}",
+        org.name,
+        org.id,
+        path.display()
+    );
+    if let Some((id, source @ config::OrgSource::Env)) = config::selected_org()
+        && id != org.id
+    {
+        println!("Note: {} is set to {} and takes precedence", source, id);
+    }
+    Ok(())
+}
+
+/// Shows the API URL, where the credentials come from and which organization is in use
+pub async fn auth_status(
+    client: &CloudClient,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let orgs = client.list_organizations().await?;
+    let selected = config::selected_org();
+
+    let (org, source) = match &selected {
+        Some((id, source)) => (orgs.iter().find(|o| &o.id == id), Some(source.to_string())),
+        None if orgs.len() == 1 => (orgs.first(), Some("only organization".to_string())),
+        None => (None, None),
+    };
+    let org_id = selected
+        .as_ref()
+        .map(|(id, _)| id.as_str())
+        .or(org.map(|o| o.id.as_str()));
+
+    if json {
+        let status = serde_json::json!({
+            "api_url": client.base_url(),
+            "credentials": client.credentials_summary(),
+            "organization": {
+                "id": org_id,
+                "name": org.map(|o| o.name.as_str()),
+                "source": source,
+            },
+            "organizations_available": orgs.len(),
+        });
+        println!("{}", serde_json::to_string_pretty(&status)?);
+        return Ok(());
+    }
+
+    println!("API URL:      {}", client.base_url());
+    println!("Credentials:  {} (valid)", client.credentials_summary());
+    match (org_id, &source) {
+        (Some(id), Some(source)) => {
+            let name = org
+                .map(|o| o.name.as_str())
+                .unwrap_or("not accessible with this key");
+            println!("Organization: {} ({}), from {}", name, id, source);
+        }
+        _ => println!(
+            "Organization: none selected ({} available). Run `chv cloud org use <id|name>`",
+            orgs.len()
+        ),
+    }
+    Ok(())
+}
+
 pub async fn org_get(
     client: &CloudClient,
     org_id: &str,
@@ -86,6 +395,7 @@ pub async fn service_get(
         Some(id) => id.to_string(),
         None => client.get_default_org_id().await?,
     };
+    let service_id = &resolve_service_id(client, &org_id, service_id).await?;
 
     let svc = client.get_service(&org_id, service_id).await?;
 
@@ -120,6 +430,339 @@ pub async fn service_get(
     Ok(())
 }
 
+/// Options for updating a service; unset fields are left as they are
+#[derive(Default)]
+pub struct UpdateServiceOptions {
+    pub name: Option<String>,
+    pub release_channel: Option<String>,
+    pub idle_scaling: Option<bool>,
+    pub idle_timeout_minutes: Option<u32>,
+    pub add_ip: Vec<String>,
+    pub remove_ip: Vec<String>,
+    /// Tags as `key=value`
+    pub add_tag: Vec<String>,
+    /// Tag keys
+    pub remove_tag: Vec<String>,
+}
+
+/// Lists the fields that differ between two versions of a service, one line per change
+fn service_changes(before: &Service, after: &Service) -> Vec<String> {
+    fn show<T: std::fmt::Display>(value: &Option<T>) -> String {
+        value
+            .as_ref()
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    let mut changes = Vec::new();
+    if before.name != after.name {
+        changes.push(format!("  name: {} → {}", before.name, after.name));
+    }
+    if before.release_channel != after.release_channel {
+        changes.push(format!(
+            "  release channel: {} → {}",
+            show(&before.release_channel),
+            show(&after.release_channel)
+        ));
+    }
+    if before.idle_scaling != after.idle_scaling {
+        changes.push(format!(
+            "  idle scaling: {} → {}",
+            show(&before.idle_scaling),
+            show(&after.idle_scaling)
+        ));
+    }
+    if before.idle_timeout_minutes != after.idle_timeout_minutes {
+        changes.push(format!(
+            "  idle timeout (minutes): {} → {}",
+            show(&before.idle_timeout_minutes),
+            show(&after.idle_timeout_minutes)
+        ));
+    }
+    let sizes = [
+        (
+            "min replica memory (GB)",
+            before.min_replica_memory_gb,
+            after.min_replica_memory_gb,
+        ),
+        (
+            "max replica memory (GB)",
+            before.max_replica_memory_gb,
+            after.max_replica_memory_gb,
+        ),
+        ("replicas", before.num_replicas, after.num_replicas),
+    ];
+    for (label, old, new) in sizes {
+        if old != new {
+            changes.push(format!("  {}: {} → {}", label, show(&old), show(&new)));
+        }
+    }
+
+    let ip_label = |e: &IpAccessEntry| match &e.description {
+        Some(desc) if !desc.is_empty() => format!("{} ({})", e.source, desc),
+        _ => e.source.clone(),
+    };
+    let old_ips = before.ip_access_list.as_deref().unwrap_or_default();
+    let new_ips = after.ip_access_list.as_deref().unwrap_or_default();
+    for ip in old_ips.iter().filter(|ip| !new_ips.contains(ip)) {
+        changes.push(format!("  - ip {}", ip_label(ip)));
+    }
+    for ip in new_ips.iter().filter(|ip| !old_ips.contains(ip)) {
+        changes.push(format!("  + ip {}", ip_label(ip)));
+    }
+
+    let old_tags = before.tags.as_deref().unwrap_or_default();
+    let new_tags = after.tags.as_deref().unwrap_or_default();
+    for tag in old_tags.iter().filter(|t| !new_tags.contains(t)) {
+        changes.push(format!("  - tag {}={}", tag.key, tag.value));
+    }
+    for tag in new_tags.iter().filter(|t| !old_tags.contains(t)) {
+        changes.push(format!("  + tag {}={}", tag.key, tag.value));
+    }
+    changes
+}
+
+/// Changes a service's settings and prints what changed. Name, release channel, IPs and
+/// tags go through the service PATCH; idle settings through the replica scaling PATCH.
+pub async fn service_update(
+    client: &CloudClient,
+    service_id: &str,
+    org_id: Option<&str>,
+    opts: UpdateServiceOptions,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let scaling = ReplicaScalingRequest {
+        idle_scaling: opts.idle_scaling,
+        idle_timeout_minutes: opts.idle_timeout_minutes,
+        ..Default::default()
+    };
+    validate_scaling(&scaling)?;
+    validate_sources(&opts.add_ip)?;
+
+    let org_id = match org_id {
+        Some(id) => id.to_string(),
+        None => client.get_default_org_id().await?,
+    };
+    let service_id = &resolve_service_id(client, &org_id, service_id).await?;
+    let before = client.get_service(&org_id, service_id).await?;
+
+    let current_ips = before.ip_access_list.as_deref().unwrap_or_default();
+    let mut remove_ips = Vec::new();
+    for source in &opts.remove_ip {
+        let entry = current_ips
+            .iter()
+            .find(|e| same_source(&e.source, source))
+            .ok_or_else(|| format!("{} is not in the IP access list of {}", source, before.name))?;
+        remove_ips.push(entry.clone());
+    }
+    let add_ips: Vec<IpAccessEntry> = opts
+        .add_ip
+        .iter()
+        .map(|source| IpAccessEntry {
+            source: source.clone(),
+            description: None,
+        })
+        .collect();
+
+    let current_tags = before.tags.as_deref().unwrap_or_default();
+    let mut remove_tags = Vec::new();
+    for key in &opts.remove_tag {
+        let tag = current_tags
+            .iter()
+            .find(|t| &t.key == key)
+            .ok_or_else(|| format!("{} has no tag {}", before.name, key))?;
+        remove_tags.push(tag.clone());
+    }
+    let mut add_tags = Vec::new();
+    for tag in &opts.add_tag {
+        let (key, value) = tag
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid tag {}, expected KEY=VALUE", tag))?;
+        add_tags.push(ResourceTag {
+            key: key.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    let request = UpdateServiceRequest {
+        name: opts.name,
+        release_channel: opts.release_channel,
+        ip_access_list: (!add_ips.is_empty() || !remove_ips.is_empty()).then_some(ListPatch {
+            add: add_ips,
+            remove: remove_ips,
+        }),
+        tags: (!add_tags.is_empty() || !remove_tags.is_empty()).then_some(ListPatch {
+            add: add_tags,
+            remove: remove_tags,
+        }),
+    };
+    let update_service = request.name.is_some()
+        || request.release_channel.is_some()
+        || request.ip_access_list.is_some()
+        || request.tags.is_some();
+    let update_scaling = scaling.idle_scaling.is_some() || scaling.idle_timeout_minutes.is_some();
+    if !update_service && !update_scaling {
+        return Err("Nothing to update. Pass at least one of --name, --release-channel, \
+                    --idle-scaling, --idle-timeout-minutes, --add-ip, --remove-ip, --add-tag, --remove-tag"
+            .into());
+    }
+
+    let mut after = None;
+    if update_service {
+        after = Some(client.update_service(&org_id, service_id, &request).await?);
+    }
+    if update_scaling {
+        match client
+            .update_replica_scaling(&org_id, service_id, &scaling)
+            .await
+        {
+            Ok(svc) => after = Some(svc),
+            Err(e) => {
+                // The service PATCH went through; say what it changed before failing
+                if let Some(applied) = &after {
+                    let changes = service_changes(&before, applied);
+                    if !changes.is_empty() {
+                        eprintln!("Applied to service {} before the error:", applied.name);
+                        for change in changes {
+                            eprintln!("{}", change);
+                        }
+                    }
+                }
+                return Err(e.into());
+            }
+        }
+    }
+    let after = after.expect("at least one update was sent");
+
+    if json {
+        let result = serde_json::json!({ "before": before, "after": after });
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    let changes = service_changes(&before, &after);
+    if changes.is_empty() {
+        println!("Service {} unchanged", after.name);
+    } else {
+        println!("Updated service {} ({}):", after.name, after.id);
+        for change in changes {
+            println!("{}", change);
+        }
+    }
+    Ok(())
+}
+
+/// Validates replica settings against the documented limits before anything is sent
+fn validate_scaling(request: &ReplicaScalingRequest) -> Result<(), String> {
+    for (flag, value) in [
+        ("--min-replica-memory-gb", request.min_replica_memory_gb),
+        ("--max-replica-memory-gb", request.max_replica_memory_gb),
+    ] {
+        if let Some(gb) = value
+            && (!(8..=356).contains(&gb) || gb % 4 != 0)
+        {
+            return Err(format!(
+                "{} must be between 8 and 356 and a multiple of 4, got {}",
+                flag, gb
+            ));
+        }
+    }
+    if let (Some(min), Some(max)) = (request.min_replica_memory_gb, request.max_replica_memory_gb)
+        && min > max
+    {
+        return Err(format!(
+            "--min-replica-memory-gb ({}) is larger than --max-replica-memory-gb ({})",
+            min, max
+        ));
+    }
+    if let Some(replicas) = request.num_replicas
+        && !(1..=20).contains(&replicas)
+    {
+        return Err(format!(
+            "--num-replicas must be between 1 and 20, got {}",
+            replicas
+        ));
+    }
+    if let Some(minutes) = request.idle_timeout_minutes
+        && minutes < MIN_IDLE_TIMEOUT_MINUTES
+    {
+        return Err(format!(
+            "--idle-timeout-minutes must be at least {}, got {}",
+            MIN_IDLE_TIMEOUT_MINUTES, minutes
+        ));
+    }
+    Ok(())
+}
+
+/// Changes a service's replica memory range and replica count
+pub async fn service_scale(
+    client: &CloudClient,
+    service_id: &str,
+    org_id: Option<&str>,
+    request: ReplicaScalingRequest,
+    wait: Option<Duration>,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if request.min_replica_memory_gb.is_none()
+        && request.max_replica_memory_gb.is_none()
+        && request.num_replicas.is_none()
+    {
+        return Err(
+            "Nothing to scale. Pass --min-replica-memory-gb, --max-replica-memory-gb \
+                    or --num-replicas"
+                .into(),
+        );
+    }
+    validate_scaling(&request)?;
+
+    let org_id = match org_id {
+        Some(id) => id.to_string(),
+        None => client.get_default_org_id().await?,
+    };
+    let service_id = &resolve_service_id(client, &org_id, service_id).await?;
+    let before = client.get_service(&org_id, service_id).await?;
+    let mut after = client
+        .update_replica_scaling(&org_id, service_id, &request)
+        .await?;
+
+    let started = Instant::now();
+    if let Some(timeout) = wait
+        && let Some(done) = wait_for_service(
+            client,
+            &org_id,
+            service_id,
+            WaitFor::Scaled(&request),
+            timeout,
+        )
+        .await?
+    {
+        after = done;
+    }
+
+    if json {
+        let result = serde_json::json!({ "before": before, "after": after });
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    let changes = service_changes(&before, &after);
+    match (wait.is_some(), changes.is_empty()) {
+        (_, true) if after.state != "running" && wait.is_none() => println!(
+            "Scaling of {} requested (state: {}); the new settings apply shortly",
+            after.name, after.state
+        ),
+        (_, true) => println!("Service {} already has these settings", after.name),
+        (true, false) => println!(
+            "Service {} scaled ({}):",
+            after.name,
+            format_elapsed(started.elapsed())
+        ),
+        (false, false) => println!("Scaling service {} ({}):", after.name, after.id),
+    }
+    for change in changes {
+        println!("{}", change);
+    }
+    Ok(())
+}
+
 /// Options for creating a service
 #[derive(Default)]
 pub struct CreateServiceOptions {
@@ -148,8 +791,18 @@ pub struct CreateServiceOptions {
 pub async fn service_create(
     client: &CloudClient,
     opts: CreateServiceOptions,
+    wait: Option<Duration>,
     json: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    validate_scaling(&ReplicaScalingRequest {
+        min_replica_memory_gb: opts.min_replica_memory_gb,
+        max_replica_memory_gb: opts.max_replica_memory_gb,
+        num_replicas: opts.num_replicas,
+        idle_timeout_minutes: opts.idle_timeout_minutes,
+        ..Default::default()
+    })?;
+    validate_sources(&opts.ip_allow)?;
+
     let org_id = match opts.org_id.as_deref() {
         Some(id) => id.to_string(),
         None => client.get_default_org_id().await?,
@@ -197,37 +850,443 @@ pub async fn service_create(
         ..Default::default()
     };
 
-    let response = client.create_service(&org_id, &request).await?;
+    let mut response = client.create_service(&org_id, &request).await?;
+
+    // The password is only returned here, so show it before waiting can fail
+    if !json {
+        print_created(&response);
+    }
+
+    if let Some(timeout) = wait {
+        let started = Instant::now();
+        let service_id = response.service.id.clone();
+        let waited = wait_for_service(
+            client,
+            &org_id,
+            &service_id,
+            WaitFor::State("running"),
+            timeout,
+        )
+        .await;
+        let svc = match waited {
+            Ok(svc) => svc,
+            // JSON output is a single document, so the credentials go into the error
+            Err(e) if json => return Err(with_credentials(e, &response)),
+            Err(e) => return Err(e),
+        };
+        if let Some(svc) = svc {
+            response.service = svc;
+        }
+        if !json {
+            println!(
+                "Service {} is running ({})",
+                response.service.name,
+                format_elapsed(started.elapsed())
+            );
+        }
+    }
 
     if json {
         println!("{}", serde_json::to_string_pretty(&response)?);
-    } else {
-        println!("Service created successfully!");
-        println!();
-        println!("Service: {}", response.service.name);
-        println!("  ID: {}", response.service.id);
-        println!("  State: {}", response.service.state);
-        println!("  Provider: {}", response.service.provider);
-        println!("  Region: {}", response.service.region);
-        if let Some(replicas) = response.service.num_replicas {
-            println!("  Replicas: {}", replicas);
-        }
-        if let Some(min_mem) = response.service.min_replica_memory_gb {
-            println!("  Min Memory/Replica: {} GB", min_mem);
-        }
-        if let Some(max_mem) = response.service.max_replica_memory_gb {
-            println!("  Max Memory/Replica: {} GB", max_mem);
-        }
-        if let Some(endpoints) = &response.service.endpoints {
-            if let Some(ep) = endpoints.first() {
-                println!("  Host: {}", ep.host);
-                println!("  Port: {}", ep.port);
+    }
+    Ok(())
+}
+
+fn print_created(response: &CreateServiceResponse) {
+    println!("Service created successfully!");
+    println!();
+    println!("Service: {}", response.service.name);
+    println!("  ID: {}", response.service.id);
+    println!("  State: {}", response.service.state);
+    println!("  Provider: {}", response.service.provider);
+    println!("  Region: {}", response.service.region);
+    if let Some(replicas) = response.service.num_replicas {
+        println!("  Replicas: {}", replicas);
+    }
+    if let Some(min_mem) = response.service.min_replica_memory_gb {
+        println!("  Min Memory/Replica: {} GB", min_mem);
+    }
+    if let Some(max_mem) = response.service.max_replica_memory_gb {
+        println!("  Max Memory/Replica: {} GB", max_mem);
+    }
+    if let Some(endpoints) = &response.service.endpoints
+        && let Some(ep) = endpoints.first()
+    {
+        println!("  Host: {}", ep.host);
+        println!("  Port: {}", ep.port);
+    }
+    println!();
+    println!("Credentials (save these, password shown only once):");
+    println!("  Username: default");
+    println!("  Password: {}", response.password);
+}
+
+/// Adds the new service's ID and one-time password to an error from waiting on it,
+/// keeping the API error details that decide the exit code
+fn with_credentials(
+    error: Box<dyn std::error::Error>,
+    response: &CreateServiceResponse,
+) -> Box<dyn std::error::Error> {
+    let note = format!(
+        "Service {} ({}) was created; default user password: {}",
+        response.service.name, response.service.id, response.password
+    );
+    match error.downcast::<CloudError>() {
+        Ok(mut e) => {
+            e.message = format!("{}. {}", e.message, note);
+            e
+        }
+        Err(e) => format!("{}. {}", e, note).into(),
+    }
+}
+
+/// Parses an IP address or CIDR block into its address and prefix length; a bare
+/// address is a single host (/32 or /128)
+fn parse_cidr(source: &str) -> Result<(IpAddr, u8), String> {
+    let (addr, prefix) = match source.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix)),
+        None => (source, None),
+    };
+    let addr: IpAddr = addr
+        .parse()
+        .map_err(|_| format!("Invalid IP address or CIDR {}", source))?;
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix = match prefix {
+        Some(prefix) => prefix
+            .parse::<u8>()
+            .ok()
+            .filter(|p| *p <= max)
+            .ok_or_else(|| format!("Invalid prefix length in {} (expected 0-{})", source, max))?,
+        None => max,
+    };
+    Ok((addr, prefix))
+}
+
+/// Checks that each source is an IP address or CIDR block
+fn validate_sources(sources: &[String]) -> Result<(), String> {
+    for source in sources {
+        parse_cidr(source)?;
+    }
+    Ok(())
+}
+
+/// Whether two sources describe the same block, so `10.0.0.1` matches `10.0.0.1/32`
+fn same_source(a: &str, b: &str) -> bool {
+    a == b || matches!((parse_cidr(a), parse_cidr(b)), (Ok(x), Ok(y)) if x == y)
+}
+
+/// Looks up the caller's public address as seen from the internet, as a single-host CIDR
+async fn lookup_my_ip(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Could not look up your public IP from {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Could not look up your public IP from {}: HTTP {}",
+            url,
+            response.status().as_u16()
+        )
+        .into());
+    }
+    let body = response.text().await?;
+    let addr: IpAddr = body.trim().parse().map_err(|_| {
+        format!(
+            "{} did not return an IP address (got {:?}); pass a URL that answers with the bare address",
+            url,
+            body.trim()
+        )
+    })?;
+    let prefix = if addr.is_ipv4() { 32 } else { 128 };
+    Ok(format!("{}/{}", addr, prefix))
+}
+
+/// Entries for an `ip add` or `ip set`: the given sources plus, with `--add-my-ip`, the
+/// caller's public address
+pub struct IpEntriesOptions {
+    pub sources: Vec<String>,
+    pub description: Option<String>,
+    pub add_my_ip: bool,
+    pub my_ip_url: Option<String>,
+}
+
+impl IpEntriesOptions {
+    async fn entries(&self) -> Result<Vec<IpAccessEntry>, Box<dyn std::error::Error>> {
+        let mut entries: Vec<IpAccessEntry> = self
+            .sources
+            .iter()
+            .map(|source| IpAccessEntry {
+                source: source.clone(),
+                description: self.description.clone(),
+            })
+            .collect();
+        if self.add_my_ip {
+            let url = self
+                .my_ip_url
+                .clone()
+                .or_else(|| std::env::var("CLICKHOUSE_CLOUD_MY_IP_URL").ok())
+                .unwrap_or_else(|| DEFAULT_MY_IP_URL.to_string());
+            let source = lookup_my_ip(&url).await?;
+            eprintln!("Your public IP is {}", source);
+            entries.push(IpAccessEntry {
+                source,
+                description: Some(
+                    self.description
+                        .clone()
+                        .unwrap_or_else(|| "Added by chv --add-my-ip".to_string()),
+                ),
+            });
+        }
+        if entries.is_empty() {
+            return Err("No IP entries given. Pass IPs or CIDR blocks, or --add-my-ip".into());
+        }
+        Ok(entries)
+    }
+}
+
+/// Prints a service's IP access list
+pub async fn ip_list(
+    client: &CloudClient,
+    service_id: &str,
+    org_id: Option<&str>,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let org_id = match org_id {
+        Some(id) => id.to_string(),
+        None => client.get_default_org_id().await?,
+    };
+    let service_id = &resolve_service_id(client, &org_id, service_id).await?;
+    let svc = client.get_service(&org_id, service_id).await?;
+    let entries = svc.ip_access_list.unwrap_or_default();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+    if entries.is_empty() {
+        println!(
+            "No IP access entries for {}; the service accepts no connections",
+            svc.name
+        );
+        return Ok(());
+    }
+    println!("{:<45} DESCRIPTION", "SOURCE");
+    for entry in &entries {
+        println!(
+            "{:<45} {}",
+            entry.source,
+            entry.description.as_deref().unwrap_or("")
+        );
+    }
+    if entries
+        .iter()
+        .any(|e| OPEN_SOURCES.contains(&e.source.as_str()))
+    {
+        eprintln!(
+            "Note: {} is open to any address; `chv cloud service ip set {} --add-my-ip` restricts it",
+            svc.name, svc.name
+        );
+    }
+    Ok(())
+}
+
+/// What an IP access list change does with the current entries
+pub enum IpChange {
+    Add(IpEntriesOptions),
+    Remove(Vec<String>),
+    /// Replace the whole list
+    Set(IpEntriesOptions),
+}
+
+/// Adds, removes or replaces IP access entries through the service PATCH and prints the
+/// entries that changed
+pub async fn ip_update(
+    client: &CloudClient,
+    service_id: &str,
+    org_id: Option<&str>,
+    change: IpChange,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match &change {
+        IpChange::Add(opts) | IpChange::Set(opts) => validate_sources(&opts.sources)?,
+        IpChange::Remove(sources) => validate_sources(sources)?,
+    }
+    let org_id = match org_id {
+        Some(id) => id.to_string(),
+        None => client.get_default_org_id().await?,
+    };
+    let service_id = &resolve_service_id(client, &org_id, service_id).await?;
+    let before = client.get_service(&org_id, service_id).await?;
+    let current = before.ip_access_list.clone().unwrap_or_default();
+
+    let patch = match change {
+        IpChange::Add(opts) => {
+            let mut add = Vec::new();
+            for entry in opts.entries().await? {
+                if current
+                    .iter()
+                    .any(|e| same_source(&e.source, &entry.source))
+                {
+                    eprintln!("{} is already in the IP access list", entry.source);
+                } else {
+                    add.push(entry);
+                }
+            }
+            ListPatch {
+                add,
+                remove: Vec::new(),
+            }
+        }
+        IpChange::Remove(sources) => {
+            let mut remove = Vec::new();
+            for source in &sources {
+                let entry = current
+                    .iter()
+                    .find(|e| same_source(&e.source, source))
+                    .ok_or_else(|| {
+                        format!("{} is not in the IP access list of {}", source, before.name)
+                    })?;
+                remove.push(entry.clone());
+            }
+            if remove.len() == current.len() {
+                eprintln!(
+                    "Warning: the IP access list of {} is now empty; the service accepts no connections",
+                    before.name
+                );
+            }
+            ListPatch {
+                add: Vec::new(),
+                remove,
+            }
+        }
+        IpChange::Set(opts) => {
+            // Without --description, an entry already in the list is kept as it is
+            let entries: Vec<IpAccessEntry> = opts
+                .entries()
+                .await?
+                .into_iter()
+                .map(|entry| {
+                    match current
+                        .iter()
+                        .find(|e| same_source(&e.source, &entry.source))
+                    {
+                        Some(existing) if opts.description.is_none() => existing.clone(),
+                        _ => entry,
+                    }
+                })
+                .collect();
+            ListPatch {
+                add: entries
+                    .iter()
+                    .filter(|e| !current.contains(e))
+                    .cloned()
+                    .collect(),
+                remove: current
+                    .iter()
+                    .filter(|e| !entries.contains(e))
+                    .cloned()
+                    .collect(),
             }
         }
-        println!();
-        println!("Credentials (save these, password shown only once):");
-        println!("  Username: default");
-        println!("  Password: {}", response.password);
+    };
+
+    let updated = if patch.add.is_empty() && patch.remove.is_empty() {
+        None
+    } else {
+        let request = UpdateServiceRequest {
+            ip_access_list: Some(patch),
+            ..Default::default()
+        };
+        Some(client.update_service(&org_id, service_id, &request).await?)
+    };
+    let after = updated.as_ref().unwrap_or(&before);
+
+    if json {
+        let result = serde_json::json!({ "before": before, "after": after });
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    let changes = service_changes(&before, after);
+    if changes.is_empty() {
+        println!("IP access list of {} unchanged", after.name);
+    } else {
+        println!("Updated IP access list of {} ({}):", after.name, after.id);
+        for change in changes {
+            println!("{}", change);
+        }
+    }
+    Ok(())
+}
+
+/// Generates a new password for a service's default user and prints it. With `save`, also
+/// stores it in .clickhouse/secrets.json so client connections to the service can use it.
+pub async fn service_reset_password(
+    client: &CloudClient,
+    service_id: &str,
+    org_id: Option<&str>,
+    save: bool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let org_id = match org_id {
+        Some(id) => id.to_string(),
+        None => client.get_default_org_id().await?,
+    };
+    let service_id = &resolve_service_id(client, &org_id, service_id).await?;
+    let svc = client.get_service(&org_id, service_id).await?;
+
+    let password = client
+        .reset_service_password(&org_id, service_id)
+        .await?
+        .password
+        .ok_or("The API did not return a password")?;
+
+    let mut saved_to = None;
+    if save {
+        let endpoint = svc.endpoints.as_deref().and_then(|eps| {
+            eps.iter()
+                .find(|e| e.protocol == "nativesecure")
+                .or_else(|| eps.first())
+        });
+        let endpoint = endpoint.ok_or_else(|| {
+            format!(
+                "Service {} has no endpoint to save the password for",
+                svc.name
+            )
+        })?;
+        secrets::save_service(ServiceSecret {
+            service_id: svc.id.clone(),
+            name: svc.name.clone(),
+            host: endpoint.host.clone(),
+            port: endpoint.port,
+            user: "default".to_string(),
+            password: password.clone(),
+        })?;
+        saved_to = Some(secrets::secrets_path());
+    }
+
+    if json {
+        let result = serde_json::json!({
+            "service_id": svc.id,
+            "username": "default",
+            "password": password,
+            "saved_to": saved_to,
+        });
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    println!("New password for {} ({}):", svc.name, svc.id);
+    println!("  Username: default");
+    println!("  Password: {}", password);
+    match saved_to {
+        Some(path) => println!(
+            "Saved to {}; `chv run client --host <host>` and --host connections use it",
+            path.display()
+        ),
+        None => println!("Shown only once; pass --save to keep it in .clickhouse/secrets.json"),
     }
     Ok(())
 }
@@ -236,14 +1295,27 @@ pub async fn service_delete(
     client: &CloudClient,
     service_id: &str,
     org_id: Option<&str>,
+    wait: Option<Duration>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let org_id = match org_id {
         Some(id) => id.to_string(),
         None => client.get_default_org_id().await?,
     };
+    let service_id = &resolve_service_id(client, &org_id, service_id).await?;
 
     client.delete_service(&org_id, service_id).await?;
-    println!("Service {} deletion initiated", service_id);
+    match wait {
+        Some(timeout) => {
+            let started = Instant::now();
+            wait_for_service(client, &org_id, service_id, WaitFor::Deleted, timeout).await?;
+            println!(
+                "Service {} deleted ({})",
+                service_id,
+                format_elapsed(started.elapsed())
+            );
+        }
+        None => println!("Service {} deletion initiated", service_id),
+    }
     Ok(())
 }
 
@@ -251,19 +1323,41 @@ pub async fn service_start(
     client: &CloudClient,
     service_id: &str,
     org_id: Option<&str>,
+    wait: Option<Duration>,
     json: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let org_id = match org_id {
         Some(id) => id.to_string(),
         None => client.get_default_org_id().await?,
     };
+    let service_id = &resolve_service_id(client, &org_id, service_id).await?;
 
-    let svc = client
+    let mut svc = client
         .change_service_state(&org_id, service_id, "start")
         .await?;
 
+    let started = Instant::now();
+    if let Some(timeout) = wait
+        && let Some(done) = wait_for_service(
+            client,
+            &org_id,
+            service_id,
+            WaitFor::State("running"),
+            timeout,
+        )
+        .await?
+    {
+        svc = done;
+    }
+
     if json {
         println!("{}", serde_json::to_string_pretty(&svc)?);
+    } else if wait.is_some() {
+        println!(
+            "Service {} is running ({})",
+            svc.name,
+            format_elapsed(started.elapsed())
+        );
     } else {
         println!("Service {} starting (state: {})", svc.name, svc.state);
     }
@@ -274,19 +1368,41 @@ pub async fn service_stop(
     client: &CloudClient,
     service_id: &str,
     org_id: Option<&str>,
+    wait: Option<Duration>,
     json: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let org_id = match org_id {
         Some(id) => id.to_string(),
         None => client.get_default_org_id().await?,
     };
+    let service_id = &resolve_service_id(client, &org_id, service_id).await?;
 
-    let svc = client
+    let mut svc = client
         .change_service_state(&org_id, service_id, "stop")
         .await?;
 
+    let started = Instant::now();
+    if let Some(timeout) = wait
+        && let Some(done) = wait_for_service(
+            client,
+            &org_id,
+            service_id,
+            WaitFor::State("stopped"),
+            timeout,
+        )
+        .await?
+    {
+        svc = done;
+    }
+
     if json {
         println!("{}", serde_json::to_string_pretty(&svc)?);
+    } else if wait.is_some() {
+        println!(
+            "Service {} is stopped ({})",
+            svc.name,
+            format_elapsed(started.elapsed())
+        );
     } else {
         println!("Service {} stopping (state: {})", svc.name, svc.state);
     }
@@ -303,6 +1419,7 @@ pub async fn backup_list(
         Some(id) => id.to_string(),
         None => client.get_default_org_id().await?,
     };
+    let service_id = &resolve_service_id(client, &org_id, service_id).await?;
 
     let backups = client.list_backups(&org_id, service_id).await?;
 
@@ -317,7 +1434,7 @@ pub async fn backup_list(
         for backup in backups {
             let size = backup
                 .size_in_bytes
-                .map(|s| format_bytes(s))
+                .map(format_bytes)
                 .unwrap_or_else(|| "-".to_string());
             let created = backup.created_at.as_deref().unwrap_or("-");
             println!("  {} - {} ({}) {}", backup.id, backup.status, size, created);
@@ -337,6 +1454,7 @@ pub async fn backup_get(
         Some(id) => id.to_string(),
         None => client.get_default_org_id().await?,
     };
+    let service_id = &resolve_service_id(client, &org_id, service_id).await?;
 
     let backup = client.get_backup(&org_id, service_id, backup_id).await?;
 
@@ -377,9 +1495,11 @@ pub fn auth_interactive() -> Result<(), Box<dyn std::error::Error>> {
         return Err("API secret cannot be empty".into());
     }
 
+    // Keep an API URL override from an existing credentials file
     let creds = Credentials {
         api_key,
         api_secret,
+        api_url: credentials::load_credentials().and_then(|c| c.api_url),
     };
     credentials::save_credentials(&creds)?;
 
@@ -390,18 +1510,93 @@ pub fn auth_interactive() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn format_bytes(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.2} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.2} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
+    fn service(id: &str, name: &str) -> Service {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "name": name, "provider": "aws", "region": "us-east-1", "state": "running"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_match_service() {
+        let services = vec![
+            service("3f1c2a00-0000-0000-0000-000000000001", "analytics"),
+            service("3f1c9b00-0000-0000-0000-000000000002", "staging"),
+            service("7d2e0000-0000-0000-0000-000000000003", "staging"),
+        ];
+        let id = |r| match_service(&services, r).map(|s| s.id.clone());
+        assert_eq!(id("analytics").unwrap(), services[0].id);
+        assert_eq!(id("3f1c9").unwrap(), services[1].id);
+        assert!(id("3f1c").unwrap_err().contains("matches 2 services"));
+        assert!(id("staging").unwrap_err().contains("matches 2 services"));
+        assert!(id("7d2").unwrap_err().starts_with("No service"));
+        assert!(is_uuid(&services[2].id));
+    }
+
+    #[test]
+    fn test_service_changes() {
+        let before = service("id", "analytics");
+        let mut after = service("id", "events");
+        after.idle_timeout_minutes = Some(15);
+        after.ip_access_list = Some(vec![IpAccessEntry {
+            source: "10.0.0.0/8".to_string(),
+            description: Some("office".to_string()),
+        }]);
+        after.tags = Some(vec![ResourceTag {
+            key: "env".to_string(),
+            value: "prod".to_string(),
+        }]);
+        assert_eq!(
+            service_changes(&before, &after),
+            vec![
+                "  name: analytics → events",
+                "  idle timeout (minutes): - → 15",
+                "  + ip 10.0.0.0/8 (office)",
+                "  + tag env=prod",
+            ]
+        );
+        assert!(service_changes(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_validate_scaling() {
+        let scaling = |min, max, replicas| ReplicaScalingRequest {
+            min_replica_memory_gb: min,
+            max_replica_memory_gb: max,
+            num_replicas: replicas,
+            ..Default::default()
+        };
+        assert!(validate_scaling(&scaling(Some(8), Some(356), Some(20))).is_ok());
+        assert!(validate_scaling(&scaling(Some(4), None, None)).is_err());
+        assert!(validate_scaling(&scaling(None, Some(30), None)).is_err());
+        assert!(validate_scaling(&scaling(Some(64), Some(32), None)).is_err());
+        assert!(validate_scaling(&scaling(None, None, Some(0))).is_err());
+        assert!(validate_scaling(&scaling(None, None, Some(21))).is_err());
+        let idle = |minutes| ReplicaScalingRequest {
+            idle_timeout_minutes: Some(minutes),
+            ..Default::default()
+        };
+        assert!(validate_scaling(&idle(4)).is_err());
+        assert!(validate_scaling(&idle(5)).is_ok());
+    }
+
+    #[test]
+    fn test_parse_cidr() {
+        assert_eq!(
+            parse_cidr("10.0.0.0/8").unwrap(),
+            ("10.0.0.0".parse().unwrap(), 8)
+        );
+        assert_eq!(parse_cidr("203.0.113.7").unwrap().1, 32);
+        assert_eq!(parse_cidr("2001:db8::/32").unwrap().1, 32);
+        assert_eq!(parse_cidr("::1").unwrap().1, 128);
+        assert!(parse_cidr("10.0.0.0/33").is_err());
+        assert!(parse_cidr("10.0.0/8").is_err());
+        assert!(parse_cidr("office").is_err());
+        assert!(same_source("203.0.113.7", "203.0.113.7/32"));
+        assert!(!same_source("203.0.113.7", "203.0.113.0/24"));
     }
 }
@@ -0,0 +1,71 @@
+use crate::init;
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Cloud settings written by `chv cloud org use`
+#[derive(Serialize, Deserialize, Default)]
+pub struct CloudConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<String>,
+}
+
+/// Project-local settings (.clickhouse/cloud.json)
+pub fn project_config_path() -> PathBuf {
+    init::local_dir().join("cloud.json")
+}
+
+/// User-wide settings (~/.clickhouse/cloud.json)
+pub fn global_config_path() -> Option<PathBuf> {
+    paths::base_dir().ok().map(|dir| dir.join("cloud.json"))
+}
+
+fn load_config(path: &Path) -> Option<CloudConfig> {
+    let data = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+pub fn save_config(path: &Path, config: &CloudConfig) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(dir) = path.parent() {
+        if dir == init::local_dir() {
+            init::ensure_local_dir()?;
+        } else {
+            std::fs::create_dir_all(dir)?;
+        }
+    }
+    std::fs::write(path, serde_json::to_string_pretty(config)?)?;
+    Ok(())
+}
+
+/// Where the organization in use was chosen
+pub enum OrgSource {
+    Env,
+    Project(PathBuf),
+    Global(PathBuf),
+}
+
+impl std::fmt::Display for OrgSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrgSource::Env => write!(f, "CLICKHOUSE_CLOUD_ORG_ID"),
+            OrgSource::Project(path) | OrgSource::Global(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Returns the selected organization ID, checking CLICKHOUSE_CLOUD_ORG_ID, then the
+/// project settings, then the user-wide settings. `--org-id` flags take precedence over all.
+pub fn selected_org() -> Option<(String, OrgSource)> {
+    if let Ok(id) = std::env::var("CLICKHOUSE_CLOUD_ORG_ID")
+        && !id.is_empty()
+    {
+        return Some((id, OrgSource::Env));
+    }
+    let project = project_config_path();
+    if let Some(id) = load_config(&project).and_then(|c| c.org_id) {
+        return Some((id, OrgSource::Project(project)));
+    }
+    let global = global_config_path()?;
+    let id = load_config(&global).and_then(|c| c.org_id)?;
+    Some((id, OrgSource::Global(global)))
+}
@@ -6,6 +6,9 @@ use std::path::PathBuf;
 pub struct Credentials {
     pub api_key: String,
     pub api_secret: String,
+    /// Overrides the Cloud API base URL, e.g. for a staging environment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
 }
 
 pub fn credentials_path() -> PathBuf {
@@ -19,11 +22,7 @@ pub fn load_credentials() -> Option<Credentials> {
 }
 
 pub fn save_credentials(creds: &Credentials) -> Result<(), Box<dyn std::error::Error>> {
-    let dir = init::local_dir();
-    if !dir.exists() {
-        std::fs::create_dir_all(&dir)?;
-        std::fs::write(dir.join(".gitignore"), "*\n")?;
-    }
+    init::ensure_local_dir()?;
 
     let path = credentials_path();
     let json = serde_json::to_string_pretty(creds)?;
@@ -1,6 +1,8 @@
 pub mod client;
 pub mod commands;
+pub mod config;
 pub mod credentials;
+pub mod secrets;
 pub mod types;
 
 pub use client::CloudClient;
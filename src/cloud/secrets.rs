@@ -0,0 +1,114 @@
+use crate::init;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Secure native protocol port of ClickHouse Cloud services
+pub const SECURE_NATIVE_PORT: u16 = 9440;
+
+/// A service password saved by `chv cloud service reset-password --save`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ServiceSecret {
+    pub service_id: String,
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Secrets {
+    services: Vec<ServiceSecret>,
+}
+
+/// Project-local service passwords (.clickhouse/secrets.json), readable only by the owner
+pub fn secrets_path() -> PathBuf {
+    init::local_dir().join("secrets.json")
+}
+
+fn load() -> Secrets {
+    std::fs::read_to_string(secrets_path())
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Stores a service's password, replacing any earlier one for the same service
+pub fn save_service(secret: ServiceSecret) -> Result<(), Box<dyn std::error::Error>> {
+    init::ensure_local_dir()?;
+
+    let mut secrets = load();
+    secrets
+        .services
+        .retain(|s| s.service_id != secret.service_id);
+    secrets.services.push(secret);
+
+    let path = secrets_path();
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // The mode only applies to new files; tighten an existing one too
+        if path.exists() {
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    let mut file = options.open(&path)?;
+    file.write_all(serde_json::to_string_pretty(&secrets)?.as_bytes())?;
+    Ok(())
+}
+
+/// Finds the saved password for a user on a service host
+pub fn find(host: &str, user: &str) -> Option<ServiceSecret> {
+    load()
+        .services
+        .into_iter()
+        .find(|s| s.host == host && s.user == user)
+}
+
+/// A saved password and the clickhouse-client arguments that go with it
+pub struct ClientSecret {
+    /// Passed through CLICKHOUSE_PASSWORD so it doesn't show up in the process list
+    pub password: String,
+    pub extra_args: Vec<String>,
+}
+
+/// Connection settings for `chv run client` when `args` connect to a saved service without
+/// a password: the password, and --secure unless a port or --secure is given
+pub fn client_secret(args: &[String]) -> Option<ClientSecret> {
+    let value = |long: &str, short: &str| {
+        args.iter().enumerate().find_map(|(i, arg)| {
+            if arg == long || arg == short {
+                args.get(i + 1).cloned()
+            } else {
+                arg.strip_prefix(&format!("{}=", long)).map(String::from)
+            }
+        })
+    };
+    let has = |flags: &[&str]| {
+        args.iter().any(|a| {
+            flags
+                .iter()
+                .any(|f| a == f || a.starts_with(&format!("{}=", f)))
+        })
+    };
+
+    let host = value("--host", "-h")?;
+    if has(&["--password"]) {
+        return None;
+    }
+    let user = value("--user", "-u").unwrap_or_else(|| "default".to_string());
+    let secret = find(&host, &user)?;
+
+    let mut extra_args = Vec::new();
+    if !has(&["--port", "--secure", "-s"]) && secret.port == SECURE_NATIVE_PORT {
+        extra_args.push("--secure".to_string());
+    }
+    Some(ClientSecret {
+        password: secret.password,
+        extra_args,
+    })
+}
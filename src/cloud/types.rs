@@ -4,13 +4,13 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Deserialize)]
 pub struct ApiResponse<T> {
     pub result: Option<T>,
-    #[allow(dead_code)]
     pub error: Option<ApiError>,
+    #[serde(rename = "requestId")]
+    pub request_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ApiError {
-    #[allow(dead_code)]
     pub code: Option<String>,
     pub message: String,
 }
@@ -42,9 +42,11 @@ pub struct Service {
     pub min_replica_memory_gb: Option<u32>,
     pub max_replica_memory_gb: Option<u32>,
     pub num_replicas: Option<u32>,
+    pub release_channel: Option<String>,
+    pub tags: Option<Vec<ResourceTag>>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct IpAccessEntry {
     pub source: String,
@@ -73,7 +75,7 @@ pub struct Backup {
 }
 
 /// Resource tag
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ResourceTag {
     pub key: String,
@@ -180,3 +182,72 @@ pub struct CreateServiceResponse {
 pub struct StateChangeRequest {
     pub command: String, // "start" or "stop"
 }
+
+/// Password change request; with no hashes set, the API generates a new password
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordChangeRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_password_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_double_sha1_hash: Option<String>,
+}
+
+/// Password change response; the password is only returned when the API generated it
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordChangeResponse {
+    pub password: Option<String>,
+}
+
+/// Entries to add to and remove from a list in a PATCH request
+#[derive(Debug, Serialize)]
+pub struct ListPatch<T> {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub add: Vec<T>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<T>,
+}
+
+/// Update service request (PATCH /services/{id})
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateServiceRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Release channel: slow, default, fast
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_channel: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip_access_list: Option<ListPatch<IpAccessEntry>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<ListPatch<ResourceTag>>,
+}
+
+/// Replica scaling request (PATCH /services/{id}/replicaScaling)
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicaScalingRequest {
+    /// Minimum memory per replica in GB (8-356, multiple of 4)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_replica_memory_gb: Option<u32>,
+
+    /// Maximum memory per replica in GB (8-356, multiple of 4)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_replica_memory_gb: Option<u32>,
+
+    /// Number of replicas (1-20)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_replicas: Option<u32>,
+
+    /// Allow scale to zero when idle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_scaling: Option<bool>,
+
+    /// Minimum idle timeout in minutes (>= 5)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_minutes: Option<u32>,
+}
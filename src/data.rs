@@ -0,0 +1,401 @@
+use crate::error::{Error, Result};
+use crate::init;
+use crate::paths;
+use crate::schema::SYSTEM_DATABASES;
+use crate::server::{self, BackgroundServer};
+use crate::units::format_bytes;
+use std::collections::BTreeSet;
+use std::path::{Component, Path};
+use std::time::Duration;
+
+/// Files in a data directory that belong to a particular server run and are never carried over
+const SKIP_FILES: &[&str] = &["status", init::SERVER_LOG, init::SERVER_ERR_LOG];
+
+/// Directories in a data directory that are regenerated on startup
+const SKIP_DIRS: &[&str] = &["preprocessed_configs", "tmp"];
+
+/// Options for `chv data migrate`
+pub struct MigrateOptions {
+    pub from: String,
+    pub to: String,
+    pub hardlink: bool,
+    pub verify: bool,
+    pub force: bool,
+    pub allow_downgrade: bool,
+}
+
+/// Totals from copying a data directory
+#[derive(Default)]
+pub struct CopyStats {
+    pub copied: u64,
+    pub linked: u64,
+    pub bytes: u64,
+}
+
+/// Returns true if a file (relative to the data dir `root`) lives inside a MergeTree data part.
+/// Part files are immutable once written, so they can be shared between versions via hardlinks.
+/// A part directory holds checksums.txt; anything else (Log-family data, mutation files,
+/// EmbeddedRocksDB files, detached parts) is always copied.
+fn is_immutable_part_file(root: &Path, rel: &Path) -> bool {
+    let parts: Vec<&str> = rel
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => s.to_str(),
+            _ => None,
+        })
+        .collect();
+    // store/<prefix>/<uuid>/<part>/<file>
+    let in_table = matches!(parts.first(), Some(&"store") | Some(&"data")) && parts.len() >= 5;
+    in_table
+        && !parts.contains(&"detached")
+        && root
+            .join(rel)
+            .parent()
+            .is_some_and(|dir| dir.join("checksums.txt").is_file())
+}
+
+/// Copies a data directory, optionally hardlinking immutable part files.
+/// Removes the partially written destination on failure.
+pub fn copy_data_dir(src: &Path, dst: &Path, hardlink: bool) -> Result<CopyStats> {
+    let mut stats = CopyStats::default();
+    std::fs::create_dir_all(dst)?;
+    // Store links hold absolute, resolved paths, so match them against resolved roots
+    let result = (|| {
+        let src = src.canonicalize()?;
+        let dst = dst.canonicalize()?;
+        copy_tree(&src, &dst, Path::new(""), hardlink, &mut stats)
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_dir_all(dst);
+        return Err(e);
+    }
+    Ok(stats)
+}
+
+fn copy_tree(
+    src_root: &Path,
+    dst_root: &Path,
+    rel: &Path,
+    hardlink: bool,
+    stats: &mut CopyStats,
+) -> Result<()> {
+    let src_dir = src_root.join(rel);
+    let dst_dir = dst_root.join(rel);
+    std::fs::create_dir_all(&dst_dir)?;
+
+    for entry in std::fs::read_dir(&src_dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let rel_path = rel.join(&name);
+        let src = entry.path();
+        let dst = dst_root.join(&rel_path);
+        let file_type = entry.file_type()?;
+        let top_level = rel.as_os_str().is_empty();
+
+        if file_type.is_symlink() {
+            // Atomic databases link data/<db>/<table> to store/; re-point links into the new dir
+            let target = std::fs::read_link(&src)?;
+            // Relative targets stay valid in the copy; absolute ones are compared resolved
+            let resolved = if target.is_absolute() {
+                target.canonicalize().unwrap_or_else(|_| target.clone())
+            } else {
+                target.clone()
+            };
+            let target = match resolved.strip_prefix(src_root) {
+                Ok(inner) => dst_root.join(inner),
+                Err(_) => target,
+            };
+            std::os::unix::fs::symlink(&target, &dst)?;
+        } else if file_type.is_dir() {
+            if top_level && SKIP_DIRS.iter().any(|d| name == *d) {
+                continue;
+            }
+            copy_tree(src_root, dst_root, &rel_path, hardlink, stats)?;
+        } else {
+            if top_level && SKIP_FILES.iter().any(|f| name == *f) {
+                continue;
+            }
+            let len = entry.metadata()?.len();
+            if hardlink && is_immutable_part_file(src_root, &rel_path) {
+                std::fs::hard_link(&src, &dst)?;
+                stats.linked += 1;
+            } else {
+                std::fs::copy(&src, &dst)?;
+                stats.copied += 1;
+            }
+            stats.bytes += len;
+        }
+    }
+    Ok(())
+}
+
+/// Decodes a file name escaped by ClickHouse (`%XX` sequences)
+fn unescape_file_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = bytes.get(i + 1..i + 3)
+            && let Ok(hex) = std::str::from_utf8(hex)
+            && let Ok(b) = u8::from_str_radix(hex, 16)
+        {
+            out.push(b);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Lists (database, table) pairs that have attached metadata in a data directory
+fn expected_tables(data_dir: &Path) -> Result<BTreeSet<(String, String)>> {
+    let mut tables = BTreeSet::new();
+    let metadata = data_dir.join("metadata");
+    if !metadata.exists() {
+        return Ok(tables);
+    }
+
+    for db_entry in std::fs::read_dir(&metadata)? {
+        let db_path = db_entry?.path();
+        if !db_path.is_dir() {
+            continue;
+        }
+        let Some(db) = db_path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let db = unescape_file_name(db);
+        if SYSTEM_DATABASES.contains(&db.as_str()) {
+            continue;
+        }
+        for table_entry in std::fs::read_dir(&db_path)? {
+            let table_path = table_entry?.path();
+            let Some(file) = table_path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if let Some(table) = file.strip_suffix(".sql") {
+                tables.insert((db.clone(), unescape_file_name(table)));
+            }
+        }
+    }
+    Ok(tables)
+}
+
+/// Starts `version` against `data_dir` and returns tables with metadata that did not attach
+fn verify(version: &str, data_dir: &Path, allow_downgrade: bool) -> Result<Vec<(String, String)>> {
+    let expected = expected_tables(data_dir)?;
+    let server =
+        BackgroundServer::start(version, data_dir, allow_downgrade, Duration::from_secs(120))?;
+
+    let excluded = SYSTEM_DATABASES
+        .iter()
+        .map(|d| format!("'{}'", d))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let output = server.query(&format!(
+        "SELECT database, name FROM system.tables WHERE database NOT IN ({})",
+        excluded
+    ))?;
+    let loaded: BTreeSet<(String, String)> = output
+        .lines()
+        .filter_map(|l| l.split_once('\t'))
+        .map(|(db, t)| (db.to_string(), t.to_string()))
+        .collect();
+
+    Ok(expected.difference(&loaded).cloned().collect())
+}
+
+fn installed(version: &str) -> Result<()> {
+    if !paths::binary_path(version)?.exists() {
+        return Err(Error::VersionNotFound(version.to_string()));
+    }
+    Ok(())
+}
+
+/// Copies the project data directory of one version to another
+pub fn migrate(opts: MigrateOptions) -> Result<()> {
+    if opts.from == opts.to {
+        return Err(Error::Data("--from and --to are the same version".into()));
+    }
+    installed(&opts.to)?;
+
+    let src = init::version_data_dir(&opts.from);
+    let dst = init::version_data_dir(&opts.to);
+
+    if !src.exists() {
+        return Err(Error::Data(format!(
+            "No data directory for version {} at {}",
+            opts.from,
+            src.display()
+        )));
+    }
+    // Older servers fail on metadata written by newer ones
+    init::check_downgrade(&src, &opts.to, opts.allow_downgrade).map_err(|e| match e {
+        Error::DataTooNew { .. } => Error::Data(format!(
+            "{}; pass --allow-downgrade to migrate it anyway",
+            e
+        )),
+        e => e,
+    })?;
+    server::ensure_stopped(&opts.from)?;
+    server::ensure_stopped(&opts.to)?;
+
+    let dst_has_data = std::fs::read_dir(&dst)
+        .map(|mut d| d.next().is_some())
+        .unwrap_or(false);
+    if dst_has_data {
+        if !opts.force {
+            return Err(Error::Data(format!(
+                "{} already contains data. Use --force to replace it",
+                dst.display()
+            )));
+        }
+        std::fs::remove_dir_all(&dst)?;
+    }
+
+    println!(
+        "{} data from {} to {}...",
+        if opts.hardlink { "Linking" } else { "Copying" },
+        opts.from,
+        opts.to
+    );
+    let stats = copy_data_dir(&src, &dst, opts.hardlink)?;
+    println!(
+        "Migrated {} ({} files copied, {} hardlinked) to {}",
+        format_bytes(stats.bytes),
+        stats.copied,
+        stats.linked,
+        dst.display()
+    );
+
+    if !opts.verify {
+        return Ok(());
+    }
+
+    println!("Starting ClickHouse {} to verify the data...", opts.to);
+    let failed = verify(&opts.to, &dst, opts.allow_downgrade)?;
+    if failed.is_empty() {
+        println!("All tables attached successfully on {}", opts.to);
+        return Ok(());
+    }
+
+    println!("Tables that failed to load on {}:", opts.to);
+    for (db, table) in &failed {
+        println!("  {}.{}", db, table);
+    }
+    Err(Error::Data(format!(
+        "{} table(s) failed to load. See {}",
+        failed.len(),
+        dst.join(init::SERVER_ERR_LOG).display()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("chv-data-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Writes `files` (relative paths) under `root` with placeholder contents
+    fn write_files(root: &Path, files: &[&str]) {
+        for file in files {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "x").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_is_immutable_part_file() {
+        let root = temp_dir("parts");
+        write_files(
+            &root,
+            &[
+                "store/abc/abc123/all_1_1_0/checksums.txt",
+                "store/abc/abc123/all_1_1_0/data.bin",
+                "store/abc/abc123/format_version.txt",
+                "store/abc/abc123/detached/all_2_2_0/checksums.txt",
+                "store/abc/abc123/detached/all_2_2_0/data.bin",
+                "store/def/def456/rocksdb/MANIFEST-000005",
+                "metadata/default/t.sql",
+            ],
+        );
+        let immutable = |rel: &str| is_immutable_part_file(&root, Path::new(rel));
+        assert!(immutable("store/abc/abc123/all_1_1_0/data.bin"));
+        assert!(!immutable("store/abc/abc123/format_version.txt"));
+        assert!(!immutable("store/abc/abc123/detached/all_2_2_0/data.bin"));
+        assert!(!immutable("store/def/def456/rocksdb/MANIFEST-000005"));
+        assert!(!immutable("metadata/default/t.sql"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_copy_data_dir_copies_rocksdb_files() {
+        use std::os::unix::fs::MetadataExt;
+        let base = temp_dir("rocksdb");
+        write_files(
+            &base.join("src"),
+            &[
+                "store/abc/abc123/all_1_1_0/checksums.txt",
+                "store/abc/abc123/all_1_1_0/data.bin",
+                "store/def/def456/rocksdb/MANIFEST-000005",
+                "store/def/def456/rocksdb/000003.log",
+            ],
+        );
+        let stats = copy_data_dir(&base.join("src"), &base.join("dst"), true).unwrap();
+        assert_eq!((stats.linked, stats.copied), (2, 2));
+
+        let ino =
+            |root: &str, rel: &str| std::fs::metadata(base.join(root).join(rel)).unwrap().ino();
+        for rel in [
+            "store/def/def456/rocksdb/MANIFEST-000005",
+            "store/def/def456/rocksdb/000003.log",
+        ] {
+            assert_ne!(ino("src", rel), ino("dst", rel), "{} was hardlinked", rel);
+        }
+        let part = "store/abc/abc123/all_1_1_0/data.bin";
+        assert_eq!(ino("src", part), ino("dst", part));
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_copy_data_dir_repoints_store_links_through_symlinked_root() {
+        let base = temp_dir("symlinked-root");
+        let real = base.join("real");
+        let part = real.join("src/store/abc/abc123/all_1_1_0");
+        std::fs::create_dir_all(&part).unwrap();
+        std::fs::write(part.join("checksums.txt"), "x").unwrap();
+        std::fs::write(part.join("data.bin"), "x").unwrap();
+        std::fs::create_dir_all(real.join("src/data/default")).unwrap();
+        // ClickHouse writes the resolved path of the store directory
+        let store = real.canonicalize().unwrap().join("src/store/abc/abc123");
+        std::os::unix::fs::symlink(&store, real.join("src/data/default/t")).unwrap();
+
+        // Copy through a symlinked project directory, as with /tmp -> /private/tmp
+        let alias = base.join("alias");
+        std::os::unix::fs::symlink(&real, &alias).unwrap();
+        let stats = copy_data_dir(&alias.join("src"), &alias.join("dst"), true).unwrap();
+        assert_eq!(stats.linked, 2);
+
+        let link = std::fs::read_link(real.join("dst/data/default/t")).unwrap();
+        assert_eq!(
+            link,
+            real.canonicalize().unwrap().join("dst/store/abc/abc123")
+        );
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_unescape_file_name() {
+        assert_eq!(unescape_file_name("my%2Dtable"), "my-table");
+        assert_eq!(unescape_file_name("plain"), "plain");
+        assert_eq!(unescape_file_name("trailing%2"), "trailing%2");
+    }
+}
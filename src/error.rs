@@ -1,3 +1,4 @@
+use crate::cloud::client::CloudError;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -40,8 +41,126 @@ pub enum Error {
     #[error("Failed to execute ClickHouse: {0}")]
     Exec(String),
 
+    #[error("Keeper error: {0}")]
+    Keeper(String),
+
+    #[error("{0}")]
+    Logs(String),
+
+    #[error("Local server error: {0}")]
+    Server(String),
+
+    #[error(
+        "Data in {dir} was written by ClickHouse {data_version}, which is newer than {version}. \
+         Older versions can fail on newer metadata"
+    )]
+    DataTooNew {
+        dir: PathBuf,
+        data_version: String,
+        version: String,
+    },
+
+    #[error("Data error: {0}")]
+    Data(String),
+
+    #[error("Query failed: {0}")]
+    Query(String),
+
+    #[error("Saved query {0} not found in clickhouse/queries/. Run `chv query --list`")]
+    QueryNotFound(String),
+
+    #[error("Schema error: {0}")]
+    Schema(String),
+
+    #[error("Snapshot error: {0}")]
+    Snapshot(String),
+
+    #[error("Migration error: {0}")]
+    Migration(String),
+
+    #[error("Seed error: {0}")]
+    Seed(String),
+
+    #[error("Test error: {0}")]
+    Test(String),
+
+    #[error("Template error: {0}")]
+    Template(String),
+
+    #[error("Format error: {0}")]
+    Format(String),
+
+    #[error("Lint failed: {0}")]
+    Lint(String),
+
     #[error("Cloud API error: {0}")]
     Cloud(String),
+
+    #[error("Cloud API authentication failed: {0}")]
+    CloudUnauthorized(CloudError),
+
+    #[error("Cloud API resource not found: {0}")]
+    CloudNotFound(CloudError),
+
+    #[error("Cloud API rate limit exceeded: {0}")]
+    CloudRateLimited(CloudError),
+
+    #[error("Cloud API unavailable: {0}")]
+    CloudUnavailable(CloudError),
+
+    #[error("Cloud API rejected the request: {0}")]
+    CloudRequest(CloudError),
+}
+
+impl From<CloudError> for Error {
+    fn from(e: CloudError) -> Self {
+        match e.status {
+            Some(401 | 403) => Error::CloudUnauthorized(e),
+            Some(404) => Error::CloudNotFound(e),
+            Some(429) => Error::CloudRateLimited(e),
+            Some(500..) => Error::CloudUnavailable(e),
+            Some(_) => Error::CloudRequest(e),
+            None if e.network => Error::CloudUnavailable(e),
+            None => Error::Cloud(e.message),
+        }
+    }
+}
+
+impl Error {
+    /// Process exit code, so scripts can tell failure kinds apart:
+    /// 1 general, 2 usage (from clap), 3 Cloud auth, 4 Cloud not found,
+    /// 5 Cloud rate limited, 6 Cloud unavailable (5xx or network), 7 Cloud request rejected (other 4xx)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::CloudUnauthorized(_) => 3,
+            Error::CloudNotFound(_) => 4,
+            Error::CloudRateLimited(_) => 5,
+            Error::CloudUnavailable(_) => 6,
+            Error::CloudRequest(_) => 7,
+            _ => 1,
+        }
+    }
+
+    /// Structured form for `--json` output: `{"error": {message, exit_code, ...}}`,
+    /// with status, code and request_id for Cloud API failures
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut error = serde_json::json!({
+            "message": self.to_string(),
+            "exit_code": self.exit_code(),
+        });
+        if let Error::CloudUnauthorized(e)
+        | Error::CloudNotFound(e)
+        | Error::CloudRateLimited(e)
+        | Error::CloudUnavailable(e)
+        | Error::CloudRequest(e) = self
+        {
+            error["message"] = e.message.clone().into();
+            error["status"] = e.status.into();
+            error["code"] = e.code.clone().into();
+            error["request_id"] = e.request_id.clone().into();
+        }
+        serde_json::json!({ "error": error })
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,123 @@
+use crate::client;
+use crate::error::{Error, Result};
+use crate::init;
+use crate::schema;
+use crate::sql;
+use std::path::Path;
+
+/// Outcome of formatting one file
+struct Formatted {
+    text: String,
+    /// Statements left as written because they contain comments
+    kept: usize,
+}
+
+/// Formats each statement of a file with `clickhouse format`, keeping comments and blank
+/// lines between statements. Statements with comments inside are left unchanged, since
+/// `clickhouse format` would drop the comments.
+fn format_text(version: &str, rel: &str, text: &str) -> Result<Formatted> {
+    let mut out = String::new();
+    let mut pos = 0;
+    let mut kept = 0;
+    for stmt in sql::split_statements(text) {
+        out.push_str(&text[pos..stmt.span.start]);
+        if sql::has_comment(&stmt.sql) {
+            out.push_str(&stmt.sql);
+            kept += 1;
+        } else {
+            let formatted = client::format_sql(version, &stmt.sql, &[]).map_err(|e| {
+                let message = match e {
+                    Error::Query(m) => m,
+                    other => other.to_string(),
+                };
+                Error::Format(format!(
+                    "{}:{}: {}",
+                    rel,
+                    sql::error_line(&stmt, &message),
+                    message
+                ))
+            })?;
+            out.push_str(formatted.trim_end());
+        }
+        pos = stmt.span.end;
+    }
+    out.push_str(&text[pos..]);
+    Ok(Formatted { text: out, kept })
+}
+
+fn relative(path: &Path) -> String {
+    path.strip_prefix(init::project_dir())
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Formats every SQL file in the project. With `check`, only reports files that would change
+/// and fails if there are any.
+pub fn run(version: &str, check: bool) -> Result<()> {
+    let files = schema::project_sql_files()?;
+    if files.is_empty() {
+        println!("No SQL files found in {}", init::project_dir().display());
+        return Ok(());
+    }
+
+    let mut changed = Vec::new();
+    let mut errors = 0;
+    for path in &files {
+        let rel = relative(path);
+        let text = std::fs::read_to_string(path)?;
+        let formatted = match format_text(version, &rel, &text) {
+            Ok(f) => f,
+            Err(Error::Format(message)) => {
+                println!("  error {}", message);
+                errors += 1;
+                continue;
+            }
+            Err(e) => {
+                println!("  error {}: {}", rel, e);
+                errors += 1;
+                continue;
+            }
+        };
+        if formatted.kept > 0 {
+            println!(
+                "  note  {}: {} statement(s) with comments left as written",
+                rel, formatted.kept
+            );
+        }
+        if formatted.text == text {
+            continue;
+        }
+        if check {
+            println!("  would reformat {}", rel);
+        } else {
+            std::fs::write(path, &formatted.text)?;
+            println!("  formatted {}", rel);
+        }
+        changed.push(rel);
+    }
+
+    if errors > 0 {
+        return Err(Error::Format(format!(
+            "{} files could not be parsed",
+            errors
+        )));
+    }
+    if check && !changed.is_empty() {
+        return Err(Error::Format(format!(
+            "{} of {} files are not formatted. Run: chv fmt",
+            changed.len(),
+            files.len()
+        )));
+    }
+    if check {
+        println!("All {} files are formatted", files.len());
+    } else {
+        println!(
+            "Formatted {} files ({} unchanged)",
+            changed.len(),
+            files.len() - changed.len()
+        );
+    }
+    Ok(())
+}
@@ -1,5 +1,42 @@
-use crate::error::Result;
-use std::path::PathBuf;
+use crate::error::{Error, Result};
+use crate::version_manager;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File in each version data directory recording which ClickHouse versions wrote to it
+pub const VERSION_STAMP: &str = "chv-version.json";
+
+/// Versions that created, last opened, and newest opened a data directory
+#[derive(Serialize, Deserialize)]
+pub struct VersionStamp {
+    pub created_by: String,
+    pub last_opened_by: String,
+    /// Newest version that ever opened the directory; never lowered by a downgrade.
+    pub newest_opened_by: String,
+}
+
+impl VersionStamp {
+    fn new(version: &str) -> Self {
+        VersionStamp {
+            created_by: version.to_string(),
+            last_opened_by: version.to_string(),
+            newest_opened_by: version.to_string(),
+        }
+    }
+
+    /// Newest version that has written to the directory
+    fn newest(&self) -> &str {
+        [
+            &self.created_by,
+            &self.last_opened_by,
+            &self.newest_opened_by,
+        ]
+        .into_iter()
+        .max_by(|a, b| version_manager::compare_versions(a, b))
+        .map(String::as_str)
+        .unwrap_or_default()
+    }
+}
 
 pub fn local_dir() -> PathBuf {
     std::env::current_dir()
@@ -35,7 +72,14 @@ pub fn init() -> Result<()> {
 
 fn create_project_scaffold() -> Result<()> {
     let dir = project_dir();
-    let subdirs = ["tables", "materialized_views", "queries", "seed"];
+    let subdirs = [
+        "tables",
+        "materialized_views",
+        "migrations",
+        "queries",
+        "seed",
+        "tests",
+    ];
 
     let mut created = false;
     for subdir in &subdirs {
@@ -49,7 +93,7 @@ fn create_project_scaffold() -> Result<()> {
 
     if created {
         println!(
-            "Created project scaffold in {}/ (tables, materialized_views, queries, seed)",
+            "Created project scaffold in {}/ (tables, materialized_views, migrations, queries, seed, tests)",
             dir.display()
         );
     }
@@ -61,18 +105,187 @@ pub fn version_data_dir(version: &str) -> PathBuf {
     local_dir().join(version)
 }
 
-pub fn ensure_initialized(version: &str) -> Result<()> {
+/// Creates the git-ignored .clickhouse/ directory if it does not exist yet.
+pub fn ensure_local_dir() -> Result<()> {
     let dir = local_dir();
     if !dir.exists() {
         std::fs::create_dir_all(&dir)?;
         std::fs::write(dir.join(".gitignore"), "*\n")?;
     }
+    Ok(())
+}
+
+pub fn ensure_initialized(version: &str) -> Result<()> {
+    ensure_local_dir()?;
     let vdir = version_data_dir(version);
     std::fs::create_dir_all(&vdir)?;
+    if read_version_stamp(&vdir).is_none() {
+        write_version_stamp(&vdir, &VersionStamp::new(version))?;
+    }
     Ok(())
 }
 
-/// Returns CLI flags that point ClickHouse data into `.clickhouse/`.
+pub fn read_version_stamp(dir: &Path) -> Option<VersionStamp> {
+    let data = std::fs::read_to_string(dir.join(VERSION_STAMP)).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn write_version_stamp(dir: &Path, stamp: &VersionStamp) -> Result<()> {
+    std::fs::write(
+        dir.join(VERSION_STAMP),
+        serde_json::to_string_pretty(stamp)?,
+    )?;
+    Ok(())
+}
+
+/// Refuses data in `dir` written by a version newer than `version`, unless `allow_downgrade`
+pub fn check_downgrade(dir: &Path, version: &str, allow_downgrade: bool) -> Result<()> {
+    if allow_downgrade {
+        return Ok(());
+    }
+    if let Some(stamp) = read_version_stamp(dir)
+        && version_manager::compare_versions(stamp.newest(), version).is_gt()
+    {
+        return Err(Error::DataTooNew {
+            dir: dir.to_path_buf(),
+            data_version: stamp.newest().to_string(),
+            version: version.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Records that `version` is opening a data directory.
+/// Refuses data written by a newer version unless `allow_downgrade` is set, since older
+/// servers fail on metadata they don't understand.
+pub fn open_data_dir(dir: &Path, version: &str, allow_downgrade: bool) -> Result<()> {
+    check_downgrade(dir, version, allow_downgrade)?;
+    let stamp = match read_version_stamp(dir) {
+        Some(stamp) => {
+            let newest = stamp.newest().to_string();
+            VersionStamp {
+                newest_opened_by: match version_manager::compare_versions(&newest, version) {
+                    std::cmp::Ordering::Greater => newest,
+                    _ => version.to_string(),
+                },
+                last_opened_by: version.to_string(),
+                created_by: stamp.created_by,
+            }
+        }
+        None => VersionStamp::new(version),
+    };
+    write_version_stamp(dir, &stamp)
+}
+
+/// Server log file written inside the version data directory
+pub const SERVER_LOG: &str = "clickhouse-server.log";
+
+/// Server error log file written inside the version data directory
+pub const SERVER_ERR_LOG: &str = "clickhouse-server.err.log";
+
+/// Returns CLI flags that point ClickHouse data and logs into `.clickhouse/`.
 pub fn server_flags() -> Vec<String> {
-    vec!["--".into(), "--path=./".into()]
+    vec![
+        "--".into(),
+        "--path=./".into(),
+        format!("--logger.log=./{}", SERVER_LOG),
+        format!("--logger.errorlog=./{}", SERVER_ERR_LOG),
+    ]
+}
+
+/// Appends config overrides to clickhouse-server arguments. Overrides must follow a single
+/// `--`; the server reads anything after a second one as positional arguments.
+pub fn push_overrides(args: &mut Vec<String>, overrides: Vec<String>) {
+    if !args.iter().any(|a| a == "--") {
+        args.push("--".into());
+    }
+    args.extend(overrides.into_iter().filter(|a| a != "--"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("chv-init-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_open_data_dir_stamps() {
+        let dir = data_dir("stamps");
+        open_data_dir(&dir, "24.8.1.1", false).unwrap();
+        let stamp = read_version_stamp(&dir).unwrap();
+        assert_eq!(stamp.created_by, "24.8.1.1");
+        assert_eq!(stamp.last_opened_by, "24.8.1.1");
+        assert_eq!(stamp.newest_opened_by, "24.8.1.1");
+
+        // Same version, then an upgrade
+        open_data_dir(&dir, "24.8.1.1", false).unwrap();
+        open_data_dir(&dir, "25.1.1.1", false).unwrap();
+        let stamp = read_version_stamp(&dir).unwrap();
+        assert_eq!(stamp.created_by, "24.8.1.1");
+        assert_eq!(stamp.last_opened_by, "25.1.1.1");
+        assert_eq!(stamp.newest_opened_by, "25.1.1.1");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_open_data_dir_downgrade() {
+        let dir = data_dir("downgrade");
+        open_data_dir(&dir, "25.1.1.1", false).unwrap();
+
+        let err = open_data_dir(&dir, "24.8.1.1", false).unwrap_err();
+        assert!(
+            matches!(err, Error::DataTooNew { ref data_version, .. } if data_version == "25.1.1.1")
+        );
+        assert_eq!(read_version_stamp(&dir).unwrap().last_opened_by, "25.1.1.1");
+        assert!(check_downgrade(&dir, "24.8.1.1", false).is_err());
+        assert!(check_downgrade(&dir, "24.8.1.1", true).is_ok());
+        assert!(check_downgrade(&dir, "25.2.1.1", false).is_ok());
+
+        // An allowed downgrade keeps the record of the newer writer
+        open_data_dir(&dir, "24.8.1.1", true).unwrap();
+        let stamp = read_version_stamp(&dir).unwrap();
+        assert_eq!(stamp.last_opened_by, "24.8.1.1");
+        assert_eq!(stamp.newest_opened_by, "25.1.1.1");
+        assert!(open_data_dir(&dir, "24.8.1.1", false).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_push_overrides() {
+        let mut args = strings(&["--config-file=my.xml"]);
+        push_overrides(&mut args, strings(&["--zookeeper.node.port=9181"]));
+        assert_eq!(
+            args,
+            strings(&["--config-file=my.xml", "--", "--zookeeper.node.port=9181"])
+        );
+
+        // A separator passed by the user is reused, not repeated
+        let mut args = strings(&["--config-file=my.xml", "--", "--tcp_port=9001"]);
+        push_overrides(&mut args, strings(&["--zookeeper.node.port=9181"]));
+        assert_eq!(
+            args,
+            strings(&[
+                "--config-file=my.xml",
+                "--",
+                "--tcp_port=9001",
+                "--zookeeper.node.port=9181"
+            ])
+        );
+
+        let mut args = Vec::new();
+        push_overrides(&mut args, server_flags());
+        push_overrides(&mut args, strings(&["--macros.replica=local"]));
+        assert_eq!(args.iter().filter(|a| *a == "--").count(), 1);
+        assert_eq!(args.last().unwrap(), "--macros.replica=local");
+    }
 }
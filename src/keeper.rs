@@ -0,0 +1,185 @@
+use crate::error::{Error, Result};
+use crate::init;
+use crate::paths;
+use crate::process;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Client port the local Keeper listens on
+pub const KEEPER_PORT: u16 = 9181;
+
+/// Raft port used by the single local Keeper node
+const RAFT_PORT: u16 = 9234;
+
+/// Returns the Keeper directory (.clickhouse/keeper/)
+pub fn keeper_dir() -> PathBuf {
+    init::local_dir().join("keeper")
+}
+
+fn config_path() -> PathBuf {
+    keeper_dir().join("keeper.xml")
+}
+
+fn pid_file() -> PathBuf {
+    keeper_dir().join("keeper.pid")
+}
+
+fn err_log_path() -> PathBuf {
+    keeper_dir().join("keeper.err.log")
+}
+
+/// Returns the PID of the running local Keeper, if any. The pid file alone isn't trusted:
+/// after Keeper exits its PID can be reused, so the process must still be this project's Keeper.
+pub fn running_pid() -> Option<u32> {
+    let config_arg = format!("--config-file={}", config_path().display());
+    process::running_pid(&pid_file())
+        .filter(|pid| process::command_line(*pid).is_some_and(|cmd| cmd.contains(&config_arg)))
+}
+
+/// Generates a minimal single-node Keeper config
+fn config_xml() -> String {
+    format!(
+        r#"<clickhouse>
+    <listen_host>127.0.0.1</listen_host>
+    <logger>
+        <level>information</level>
+        <log>./keeper.log</log>
+        <errorlog>./keeper.err.log</errorlog>
+        <console>0</console>
+    </logger>
+    <keeper_server>
+        <tcp_port>{port}</tcp_port>
+        <server_id>1</server_id>
+        <log_storage_path>./coordination/log</log_storage_path>
+        <snapshot_storage_path>./coordination/snapshots</snapshot_storage_path>
+        <coordination_settings>
+            <operation_timeout_ms>10000</operation_timeout_ms>
+            <session_timeout_ms>30000</session_timeout_ms>
+        </coordination_settings>
+        <raft_configuration>
+            <server>
+                <id>1</id>
+                <hostname>127.0.0.1</hostname>
+                <port>{raft_port}</port>
+            </server>
+        </raft_configuration>
+    </keeper_server>
+</clickhouse>
+"#,
+        port = KEEPER_PORT,
+        raft_port = RAFT_PORT,
+    )
+}
+
+/// Starts the local Keeper in the background using the given version's binary.
+/// Returns the PID of the Keeper process.
+pub fn start(version: &str) -> Result<u32> {
+    if let Some(pid) = running_pid() {
+        return Err(Error::Keeper(format!(
+            "Keeper is already running (pid {})",
+            pid
+        )));
+    }
+
+    let binary = paths::binary_path(version)?;
+    if !binary.exists() {
+        return Err(Error::VersionNotFound(version.to_string()));
+    }
+
+    init::ensure_local_dir()?;
+    let dir = keeper_dir();
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(config_path(), config_xml())?;
+
+    // Launch through a shell that backgrounds Keeper and exits, so Keeper is re-parented to
+    // init and reaped there, rather than left to whichever process chv execs into next
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(r#""$0" "$@" </dev/null >keeper.stdout.log 2>&1 & echo $!"#)
+        .arg(&binary)
+        .arg("keeper")
+        .arg(format!("--config-file={}", config_path().display()))
+        .current_dir(&dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .process_group(0)
+        .output()
+        .map_err(|e| Error::Exec(e.to_string()))?;
+    let pid: u32 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .map_err(|_| Error::Keeper("Could not start Keeper in the background".into()))?;
+    process::write_pid(&pid_file(), pid)?;
+
+    // Give Keeper a moment to fail fast on bad config or a busy port
+    std::thread::sleep(Duration::from_millis(500));
+    if running_pid().is_none() {
+        let _ = std::fs::remove_file(pid_file());
+        let log = std::fs::read_to_string(err_log_path()).unwrap_or_default();
+        let tail: Vec<&str> = log.lines().rev().take(5).collect();
+        let tail: Vec<&str> = tail.into_iter().rev().collect();
+        return Err(Error::Keeper(format!(
+            "Keeper exited immediately\n{}",
+            tail.join("\n")
+        )));
+    }
+    Ok(pid)
+}
+
+/// Starts the local Keeper unless it is already running
+pub fn ensure_running(version: &str) -> Result<()> {
+    if running_pid().is_none() {
+        let pid = start(version)?;
+        println!(
+            "Started ClickHouse Keeper on port {} (pid {})",
+            KEEPER_PORT, pid
+        );
+    }
+    Ok(())
+}
+
+/// Stops the local Keeper if it is running
+pub fn stop() -> Result<()> {
+    let Some(pid) = running_pid() else {
+        let _ = std::fs::remove_file(pid_file());
+        println!("Keeper is not running");
+        return Ok(());
+    };
+
+    if !process::terminate(pid, Duration::from_secs(10)) {
+        return Err(Error::Keeper(format!(
+            "Keeper (pid {}) did not stop within 10 seconds",
+            pid
+        )));
+    }
+
+    let _ = std::fs::remove_file(pid_file());
+    println!("Stopped ClickHouse Keeper (pid {})", pid);
+    Ok(())
+}
+
+/// Prints whether the local Keeper is running
+pub fn status() -> Result<()> {
+    match running_pid() {
+        Some(pid) => {
+            println!("Keeper is running (pid {})", pid);
+            println!("  Port: {}", KEEPER_PORT);
+            println!("  Data: {}", keeper_dir().display());
+        }
+        None => println!("Keeper is not running"),
+    }
+    Ok(())
+}
+
+/// Returns config overrides that point a server's zookeeper section at the local Keeper.
+/// Also defines the {shard} and {replica} macros so `ReplicatedMergeTree()` works without arguments.
+pub fn server_flags() -> Vec<String> {
+    vec![
+        "--zookeeper.node.host=127.0.0.1".into(),
+        format!("--zookeeper.node.port={}", KEEPER_PORT),
+        "--macros.shard=01".into(),
+        "--macros.replica=local".into(),
+    ]
+}
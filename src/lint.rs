@@ -0,0 +1,236 @@
+use crate::client;
+use crate::error::{Error, Result};
+use crate::init;
+use crate::schema;
+use crate::sql::{self, CreateInfo, ObjectKind, Statement, Token};
+
+/// Clauses that end a MergeTree sorting key expression
+const KEY_TERMINATORS: &[&str] = &[
+    "PARTITION",
+    "PRIMARY",
+    "SAMPLE",
+    "TTL",
+    "SETTINGS",
+    "COMMENT",
+];
+
+/// A problem found in a file
+struct Finding {
+    line: usize,
+    rule: &'static str,
+    message: String,
+}
+
+/// Returns the tokens of a top-level `ORDER BY` (or `PRIMARY KEY`) clause
+fn key_clause<'a>(tokens: &'a [Token], first: &str, second: &str) -> Option<&'a [Token]> {
+    let mut depth = 0;
+    let mut start = None;
+    for (i, t) in tokens.iter().enumerate() {
+        if t.is_punct("(") {
+            depth += 1;
+        } else if t.is_punct(")") {
+            depth -= 1;
+        } else if depth == 0 {
+            if let Some(s) = start
+                && KEY_TERMINATORS.iter().any(|kw| t.is_kw(kw))
+                && !(t.is_kw(first) && tokens.get(i + 1).is_some_and(|n| n.is_kw(second)))
+            {
+                return Some(&tokens[s..i]);
+            }
+            if start.is_none()
+                && t.is_kw(first)
+                && tokens.get(i + 1).is_some_and(|n| n.is_kw(second))
+            {
+                start = Some(i + 2);
+            }
+        }
+    }
+    start.map(|s| &tokens[s..])
+}
+
+/// Checks a CREATE statement against the project rules
+fn check_create(stmt: &Statement, info: &CreateInfo, body: &[Token]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if info.kind == ObjectKind::MaterializedView && info.to.is_none() {
+        findings.push(Finding {
+            line: stmt.line,
+            rule: "mv-without-to",
+            message: format!(
+                "materialized view {} has no TO table; its data lives in a hidden .inner table \
+                 that is hard to query, alter and migrate",
+                info.name
+            ),
+        });
+    }
+
+    if info.kind != ObjectKind::Table {
+        return findings;
+    }
+    let engine = body
+        .iter()
+        .position(|t| t.is_kw("ENGINE"))
+        .and_then(|i| body.get(i + 2))
+        .map(|t| t.text.as_str())
+        .unwrap_or("");
+    if !engine.ends_with("MergeTree") {
+        return findings;
+    }
+
+    let Some(order_by) = key_clause(body, "ORDER", "BY") else {
+        findings.push(Finding {
+            line: stmt.line,
+            rule: "no-order-by",
+            message: format!("table {} has no ORDER BY", info.name),
+        });
+        return findings;
+    };
+    if sql::render(order_by) == "tuple()" {
+        findings.push(Finding {
+            line: stmt.line,
+            rule: "no-order-by",
+            message: format!(
+                "table {} has an empty sorting key (ORDER BY tuple())",
+                info.name
+            ),
+        });
+    }
+
+    // Nullable columns used in ORDER BY or PRIMARY KEY
+    let mut nullable: Vec<&str> = Vec::new();
+    if body.first().is_some_and(|t| t.is_punct("("))
+        && let Some(end) = sql::matching_paren(body)
+    {
+        for column in sql::split_top_level(&body[1..end]) {
+            if let Some((name, ty)) = column.split_first()
+                && ty
+                    .iter()
+                    .take_while(|t| !t.is_kw("DEFAULT"))
+                    .any(|t| t.is_kw("Nullable"))
+            {
+                nullable.push(name.text.as_str());
+            }
+        }
+    }
+    let primary_key = key_clause(body, "PRIMARY", "KEY").unwrap_or(&[]);
+    let mut reported: Vec<&str> = Vec::new();
+    for t in order_by.iter().chain(primary_key) {
+        if t.is_ident()
+            && nullable.contains(&t.text.as_str())
+            && !reported.contains(&t.text.as_str())
+        {
+            reported.push(t.text.as_str());
+            findings.push(Finding {
+                line: stmt.line,
+                rule: "nullable-sort-key",
+                message: format!(
+                    "column {} of table {} is Nullable but used in the sorting key",
+                    t.text, info.name
+                ),
+            });
+        }
+    }
+    findings
+}
+
+/// Checks one file: parse errors through `clickhouse format`, then the project rules
+fn check_file(version: &str, text: &str) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for stmt in sql::split_statements(text) {
+        if let Err(e) = client::format_sql(version, &stmt.sql, &["--oneline"]) {
+            let message = match e {
+                Error::Query(m) => m,
+                other => other.to_string(),
+            };
+            findings.push(Finding {
+                line: sql::error_line(&stmt, &message),
+                rule: "parse-error",
+                message: message.lines().next().unwrap_or("").to_string(),
+            });
+            continue;
+        }
+        if let Some((info, body)) = sql::parse_create_parts(&stmt.sql) {
+            findings.extend(check_create(&stmt, &info, &body));
+        }
+    }
+    findings
+}
+
+/// Lints every SQL file in the project and fails if any problem is found
+pub fn run(version: &str) -> Result<()> {
+    let files = schema::project_sql_files()?;
+    if files.is_empty() {
+        println!("No SQL files found in {}", init::project_dir().display());
+        return Ok(());
+    }
+
+    let mut problems = 0;
+    let mut bad_files = 0;
+    for path in &files {
+        let rel = path
+            .strip_prefix(init::project_dir())
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        let findings = check_file(version, &std::fs::read_to_string(path)?);
+        if !findings.is_empty() {
+            bad_files += 1;
+        }
+        for f in &findings {
+            println!("{}:{}: [{}] {}", rel, f.line, f.rule, f.message);
+        }
+        problems += findings.len();
+    }
+
+    if problems > 0 {
+        return Err(Error::Lint(format!(
+            "{} problems in {} of {} files",
+            problems,
+            bad_files,
+            files.len()
+        )));
+    }
+    println!("No problems found in {} files", files.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(sql: &str) -> Vec<&'static str> {
+        let stmt = sql::split_statements(sql).remove(0);
+        let (info, body) = sql::parse_create_parts(&stmt.sql).unwrap();
+        check_create(&stmt, &info, &body)
+            .into_iter()
+            .map(|f| f.rule)
+            .collect()
+    }
+
+    #[test]
+    fn test_rules() {
+        assert!(
+            rules(
+                "CREATE TABLE t (id UInt64, v Nullable(String)) ENGINE = MergeTree \
+             ORDER BY id SETTINGS index_granularity = 8192"
+            )
+            .is_empty()
+        );
+        assert_eq!(
+            rules("CREATE TABLE t (id UInt64) ENGINE = MergeTree PARTITION BY id"),
+            vec!["no-order-by"]
+        );
+        assert_eq!(
+            rules(
+                "CREATE TABLE t (id Nullable(UInt64), ts DateTime) ENGINE = ReplacingMergeTree \
+                 ORDER BY (ts, id) PARTITION BY toYYYYMM(ts)"
+            ),
+            vec!["nullable-sort-key"]
+        );
+        assert!(rules("CREATE TABLE t (id UInt64) ENGINE = Memory").is_empty());
+        assert_eq!(
+            rules("CREATE MATERIALIZED VIEW mv ENGINE = MergeTree ORDER BY x AS SELECT 1 AS x"),
+            vec!["mv-without-to"]
+        );
+    }
+}
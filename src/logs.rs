@@ -0,0 +1,365 @@
+use crate::error::{Error, Result};
+use crate::init;
+use serde::Serialize;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Options for `chv logs`
+pub struct LogOptions {
+    pub follow: bool,
+    pub level: Option<String>,
+    pub since: Option<String>,
+    pub grep: Option<String>,
+    pub lines: usize,
+    pub json: bool,
+}
+
+/// A single server log record. Multi-line messages (e.g. stack traces) are kept together.
+#[derive(Debug, Serialize)]
+pub struct LogRecord {
+    pub timestamp: String,
+    pub thread_id: Option<u64>,
+    pub query_id: Option<String>,
+    pub level: String,
+    pub logger: String,
+    pub message: String,
+    #[serde(skip)]
+    raw: String,
+}
+
+/// Returns the rank of a log level (lower is more severe). Accepts common aliases.
+pub fn level_rank(level: &str) -> Option<u8> {
+    match level.to_ascii_lowercase().as_str() {
+        "fatal" => Some(1),
+        "critical" => Some(2),
+        "error" | "err" => Some(3),
+        "warning" | "warn" => Some(4),
+        "notice" => Some(5),
+        "information" | "info" => Some(6),
+        "debug" => Some(7),
+        "trace" => Some(8),
+        "test" => Some(9),
+        _ => None,
+    }
+}
+
+/// Parses a duration such as "30s", "10m", "2h" or "1d"
+pub fn parse_duration(spec: &str) -> Option<Duration> {
+    let spec = spec.trim();
+    let split = spec.find(|c: char| !c.is_ascii_digit())?;
+    let (num, unit) = spec.split_at(split);
+    let n: u64 = num.parse().ok()?;
+    let secs = match unit {
+        "s" => n,
+        "m" => n.checked_mul(60)?,
+        "h" => n.checked_mul(3600)?,
+        "d" => n.checked_mul(86400)?,
+        _ => return None,
+    };
+    Some(Duration::from_secs(secs))
+}
+
+/// Parses the first line of a log record:
+/// `2025.01.15 10:23:45.123456 [ 12345 ] {query_id} <Information> Logger: message`
+fn parse_line(line: &str) -> Option<LogRecord> {
+    let date = line.get(..10)?;
+    let is_date = date.bytes().enumerate().all(|(i, b)| {
+        if i == 4 || i == 7 {
+            b == b'.'
+        } else {
+            b.is_ascii_digit()
+        }
+    });
+    if !is_date {
+        return None;
+    }
+
+    let (timestamp, rest) = line.split_at(line.get(11..)?.find(' ')? + 11);
+    let rest = rest.trim_start();
+
+    let (thread_id, rest) = match rest.strip_prefix('[') {
+        Some(r) => {
+            let end = r.find(']')?;
+            (r[..end].trim().parse().ok(), r[end + 1..].trim_start())
+        }
+        None => (None, rest),
+    };
+
+    let (query_id, rest) = match rest.strip_prefix('{') {
+        Some(r) => {
+            let end = r.find('}')?;
+            let id = &r[..end];
+            let id = (!id.is_empty()).then(|| id.to_string());
+            (id, r[end + 1..].trim_start())
+        }
+        None => (None, rest),
+    };
+
+    let r = rest.strip_prefix('<')?;
+    let end = r.find('>')?;
+    let level = r[..end].to_string();
+    let rest = r[end + 1..].trim_start();
+
+    let (logger, message) = match rest.split_once(": ") {
+        Some((logger, message)) => (logger.to_string(), message.to_string()),
+        None => (String::new(), rest.to_string()),
+    };
+
+    Some(LogRecord {
+        timestamp: timestamp.to_string(),
+        thread_id,
+        query_id,
+        level,
+        logger,
+        message,
+        raw: line.to_string(),
+    })
+}
+
+/// Groups log lines into records, attaching continuation lines to the preceding record
+#[derive(Default)]
+struct RecordReader {
+    pending: Option<LogRecord>,
+}
+
+impl RecordReader {
+    /// Feeds complete lines and returns records that are known to be finished
+    fn feed(&mut self, text: &str) -> Vec<LogRecord> {
+        let mut done = Vec::new();
+        for line in text.lines() {
+            match parse_line(line) {
+                Some(record) => {
+                    if let Some(prev) = self.pending.replace(record) {
+                        done.push(prev);
+                    }
+                }
+                None => {
+                    if let Some(prev) = self.pending.as_mut() {
+                        prev.message.push('\n');
+                        prev.message.push_str(line);
+                        prev.raw.push('\n');
+                        prev.raw.push_str(line);
+                    }
+                }
+            }
+        }
+        done
+    }
+
+    fn flush(&mut self) -> Option<LogRecord> {
+        self.pending.take()
+    }
+}
+
+struct Filter {
+    max_rank: Option<u8>,
+    cutoff: Option<String>,
+    grep: Option<String>,
+}
+
+impl Filter {
+    fn matches(&self, record: &LogRecord) -> bool {
+        if let Some(max) = self.max_rank
+            && level_rank(&record.level).is_some_and(|r| r > max)
+        {
+            return false;
+        }
+        if let Some(cutoff) = &self.cutoff
+            && record.timestamp.get(..cutoff.len()).unwrap_or("") < cutoff.as_str()
+        {
+            return false;
+        }
+        if let Some(pattern) = &self.grep
+            && !record.raw.contains(pattern.as_str())
+        {
+            return false;
+        }
+        true
+    }
+}
+
+fn print_record(record: &LogRecord, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string(record)?);
+    } else {
+        println!("{}", record.raw);
+    }
+    Ok(())
+}
+
+/// Returns the server log file for a version, falling back to the error log
+fn log_file(version: &str) -> Result<PathBuf> {
+    let dir = init::version_data_dir(version);
+    [init::SERVER_LOG, init::SERVER_ERR_LOG]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|p| p.exists())
+        .ok_or_else(|| {
+            Error::Logs(format!(
+                "No server logs found in {}. Start a server with `chv run server`",
+                dir.display()
+            ))
+        })
+}
+
+/// Prints (and optionally follows) the local server logs for a version
+pub fn show(version: &str, opts: LogOptions) -> Result<()> {
+    let max_rank = match opts.level.as_deref() {
+        Some(level) => Some(level_rank(level).ok_or_else(|| {
+            Error::Logs(format!(
+                "Unknown log level: {} (expected fatal, critical, error, warning, notice, information, debug, trace)",
+                level
+            ))
+        })?),
+        None => None,
+    };
+
+    let cutoff = match opts.since.as_deref() {
+        Some(spec) => {
+            let d = parse_duration(spec).ok_or_else(|| {
+                Error::Logs(format!(
+                    "Invalid --since value: {} (expected e.g. 30s, 10m, 2h, 1d)",
+                    spec
+                ))
+            })?;
+            let too_large = || Error::Logs(format!("--since value too large: {}", spec));
+            let d = chrono::Duration::from_std(d).map_err(|_| too_large())?;
+            let cutoff = chrono::Local::now()
+                .checked_sub_signed(d)
+                .ok_or_else(too_large)?;
+            Some(cutoff.format("%Y.%m.%d %H:%M:%S").to_string())
+        }
+        None => None,
+    };
+
+    let filter = Filter {
+        max_rank,
+        cutoff,
+        grep: opts.grep,
+    };
+
+    let path = log_file(version)?;
+    let mut file = std::fs::File::open(&path)?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    let mut pos = buf.len() as u64;
+
+    let mut reader = RecordReader::default();
+    let mut records: Vec<LogRecord> = reader
+        .feed(&String::from_utf8_lossy(&buf))
+        .into_iter()
+        .filter(|r| filter.matches(r))
+        .collect();
+    if let Some(last) = reader.flush().filter(|r| filter.matches(r)) {
+        records.push(last);
+    }
+
+    let skip = records.len().saturating_sub(opts.lines);
+    for record in &records[skip..] {
+        print_record(record, opts.json)?;
+    }
+
+    if !opts.follow {
+        return Ok(());
+    }
+
+    follow(&path, &mut pos, &filter, opts.json)
+}
+
+fn follow(path: &Path, pos: &mut u64, filter: &Filter, json: bool) -> Result<()> {
+    let mut reader = RecordReader::default();
+    let mut partial = String::new();
+
+    loop {
+        std::thread::sleep(Duration::from_millis(500));
+
+        let len = match std::fs::metadata(path) {
+            Ok(m) => m.len(),
+            Err(_) => continue,
+        };
+
+        // The log was rotated or truncated; start over from the beginning
+        if len < *pos {
+            *pos = 0;
+            partial.clear();
+        }
+
+        if len == *pos {
+            if let Some(record) = reader.flush().filter(|r| filter.matches(r)) {
+                print_record(&record, json)?;
+            }
+            continue;
+        }
+
+        let mut file = std::fs::File::open(path)?;
+        file.seek(SeekFrom::Start(*pos))?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        *pos += buf.len() as u64;
+
+        partial.push_str(&String::from_utf8_lossy(&buf));
+        let Some(end) = partial.rfind('\n') else {
+            continue;
+        };
+        let complete: String = partial.drain(..=end).collect();
+
+        for record in reader.feed(&complete) {
+            if filter.matches(&record) {
+                print_record(&record, json)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let line =
+            "2025.01.15 10:23:45.123456 [ 12345 ] {abc-123} <Warning> executeQuery: Slow query";
+        let record = parse_line(line).unwrap();
+        assert_eq!(record.timestamp, "2025.01.15 10:23:45.123456");
+        assert_eq!(record.thread_id, Some(12345));
+        assert_eq!(record.query_id.as_deref(), Some("abc-123"));
+        assert_eq!(record.level, "Warning");
+        assert_eq!(record.logger, "executeQuery");
+        assert_eq!(record.message, "Slow query");
+    }
+
+    #[test]
+    fn test_parse_line_without_query_id() {
+        let line =
+            "2025.01.15 10:23:45.123456 [ 1 ] {} <Information> Application: Ready for connections.";
+        let record = parse_line(line).unwrap();
+        assert_eq!(record.query_id, None);
+        assert_eq!(record.logger, "Application");
+        assert!(parse_line("0. Poco::Exception::Exception()").is_none());
+    }
+
+    #[test]
+    fn test_continuation_lines() {
+        let text = "2025.01.15 10:23:45.123456 [ 1 ] {} <Error> Application: Boom\n\
+                    0. frame one\n\
+                    2025.01.15 10:23:46.000000 [ 1 ] {} <Information> Application: Next\n";
+        let mut reader = RecordReader::default();
+        let done = reader.feed(text);
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].message, "Boom\n0. frame one");
+        assert_eq!(reader.flush().unwrap().message, "Next");
+    }
+
+    #[test]
+    fn test_level_rank_and_duration() {
+        assert!(level_rank("warning") < level_rank("Information"));
+        assert_eq!(level_rank("warn"), level_rank("Warning"));
+        assert_eq!(level_rank("loud"), None);
+        assert_eq!(parse_duration("10m"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("m"), None);
+        assert_eq!(parse_duration("99999999999999999d"), None);
+    }
+}
@@ -1,29 +1,53 @@
 mod cli;
+mod client;
 mod cloud;
+mod data;
 mod error;
+mod fmt;
 mod init;
+mod keeper;
+mod lint;
+mod logs;
+mod migrate;
 mod paths;
+mod process;
+mod query;
+mod schema;
+mod seed;
+mod server;
+mod snapshot;
+mod sql;
+mod sqltest;
+mod template;
+mod units;
 mod version_manager;
 
 use clap::Parser;
 use cli::{
-    BackupCommands, CloudArgs, CloudCommands, Cli, Commands, OrgCommands, RunArgs, RunCommands,
-    ServiceCommands,
+    AuthCommands, BackupCommands, Cli, CloudArgs, CloudCommands, Commands, DataCommands,
+    IpCommands, KeeperCommands, MigrateCommands, OrgCommands, RunArgs, RunCommands, SchemaCommands,
+    ServiceCommands, SnapshotCommands,
 };
 use cloud::CloudClient;
 use error::{Error, Result};
 use std::os::unix::process::CommandExt;
 use std::process::Command;
+use std::time::Duration;
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
+    let json_errors = matches!(&cli.command, Commands::Cloud(args) if args.json);
 
     let result = run(cli.command).await;
 
     if let Err(e) = result {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+        if json_errors {
+            println!("{}", e.to_json());
+        } else {
+            eprintln!("Error: {}", e);
+        }
+        std::process::exit(e.exit_code());
     }
 }
 
@@ -40,11 +64,164 @@ async fn run(cmd: Commands) -> Result<()> {
         Commands::Use { version } => use_version(&version).await,
         Commands::Remove { version } => remove(&version),
         Commands::Which => which(),
-        Commands::Init => {
+        Commands::Init {
+            template,
+            list_templates,
+        } => {
+            if list_templates {
+                template::list();
+                return Ok(());
+            }
+            if let Some(spec) = &template {
+                template::validate(spec)?;
+            }
             init::init()?;
+            if let Some(spec) = template {
+                template::apply(&spec)?;
+            }
             Ok(())
         }
         Commands::Run(args) => run_clickhouse(args),
+        Commands::Logs {
+            follow,
+            level,
+            since,
+            grep,
+            lines,
+            json,
+        } => {
+            let version = version_manager::get_default_version()?;
+            let opts = logs::LogOptions {
+                follow,
+                level,
+                since,
+                grep,
+                lines,
+                json,
+            };
+            logs::show(&version, opts)
+        }
+        Commands::Data { command } => match command {
+            DataCommands::Migrate {
+                from,
+                to,
+                hardlink,
+                verify,
+                force,
+                allow_downgrade,
+            } => {
+                let to = match to {
+                    Some(v) => v,
+                    None => version_manager::get_default_version()?,
+                };
+                data::migrate(data::MigrateOptions {
+                    from,
+                    to,
+                    hardlink,
+                    verify,
+                    force,
+                    allow_downgrade,
+                })
+            }
+        },
+        Commands::Snapshot { command } => match command {
+            SnapshotCommands::Save { name, force } => {
+                let version = version_manager::get_default_version()?;
+                snapshot::save(&version, &name, force)
+            }
+            SnapshotCommands::Restore { name } => {
+                let version = version_manager::get_default_version()?;
+                snapshot::restore(&version, &name)
+            }
+            SnapshotCommands::List => snapshot::list(),
+            SnapshotCommands::Rm { name } => snapshot::remove(&name),
+        },
+        Commands::Schema { command } => match command {
+            SchemaCommands::Apply { conn, dry_run } => {
+                let version = version_manager::get_default_version()?;
+                let client = client::Client::new(&version, &conn)?;
+                schema::apply(&client, dry_run)
+            }
+            SchemaCommands::Diff { conn } => {
+                let version = version_manager::get_default_version()?;
+                let client = client::Client::new(&version, &conn)?;
+                schema::diff(&client, &version)
+            }
+            SchemaCommands::Dump { conn, force } => {
+                let version = version_manager::get_default_version()?;
+                let client = client::Client::new(&version, &conn)?;
+                schema::dump(&client, &version, conn.database.as_deref(), force)
+            }
+            SchemaCommands::Graph {
+                format,
+                downstream,
+                server,
+                conn,
+            } => {
+                let client = if server {
+                    let version = version_manager::get_default_version()?;
+                    Some(client::Client::new(&version, &conn)?)
+                } else {
+                    None
+                };
+                schema::graph(client.as_ref(), &format, downstream.as_deref())
+            }
+        },
+        Commands::Migrate { command } => match command {
+            MigrateCommands::New { name } => migrate::new(&name),
+            MigrateCommands::Up { conn, to } => {
+                let version = version_manager::get_default_version()?;
+                let client = client::Client::new(&version, &conn)?;
+                migrate::up(&client, to.as_deref())
+            }
+            MigrateCommands::Down { conn, steps } => {
+                let version = version_manager::get_default_version()?;
+                let client = client::Client::new(&version, &conn)?;
+                migrate::down(&client, steps)
+            }
+            MigrateCommands::Status { conn } => {
+                let version = version_manager::get_default_version()?;
+                let client = client::Client::new(&version, &conn)?;
+                migrate::status(&client)
+            }
+        },
+        Commands::Seed { conn, truncate } => {
+            let version = version_manager::get_default_version()?;
+            let client = client::Client::new(&version, &conn)?;
+            seed::run(&client, truncate)
+        }
+        Commands::Query {
+            name,
+            params,
+            format,
+            local,
+            list,
+            conn,
+        } => match name {
+            Some(name) if !list => {
+                let version = version_manager::get_default_version()?;
+                let client = if local {
+                    None
+                } else {
+                    Some(client::Client::new(&version, &conn)?)
+                };
+                query::run(&version, client.as_ref(), &name, &params, &format)
+            }
+            _ => query::list(),
+        },
+        Commands::Test { names, update } => {
+            let version = version_manager::get_default_version()?;
+            sqltest::run(&version, &names, update)
+        }
+        Commands::Fmt { check } => {
+            let version = version_manager::get_default_version()?;
+            fmt::run(&version, check)
+        }
+        Commands::Lint => {
+            let version = version_manager::get_default_version()?;
+            lint::run(&version)
+        }
+        Commands::Keeper { command } => run_keeper(command),
         Commands::Cloud(args) => run_cloud(args).await,
     }
 }
@@ -132,11 +309,11 @@ fn remove(version: &str) -> Result<()> {
     }
 
     // Check if this is the default version
-    if let Ok(default) = version_manager::get_default_version() {
-        if default == version {
-            let default_file = paths::default_file()?;
-            let _ = std::fs::remove_file(default_file);
-        }
+    if let Ok(default) = version_manager::get_default_version()
+        && default == version
+    {
+        let default_file = paths::default_file()?;
+        let _ = std::fs::remove_file(default_file);
     }
 
     std::fs::remove_dir_all(&version_dir)?;
@@ -169,24 +346,44 @@ fn run_clickhouse(args: RunArgs) -> Result<()> {
 
     // Otherwise, handle subcommands
     match args.command {
-        Some(RunCommands::Server { args }) => {
+        Some(RunCommands::Server {
+            keeper,
+            allow_downgrade,
+            args,
+        }) => {
             let has_config = args
                 .iter()
                 .any(|a| a.starts_with("--config-file") || a.starts_with("-C"));
             let mut cmd = Command::new(&binary);
             cmd.arg("server");
-            cmd.args(&args);
+            let mut server_args = args;
             if !has_config {
                 init::ensure_initialized(&version)?;
-                cmd.current_dir(init::version_data_dir(&version));
-                cmd.args(init::server_flags());
+                let data_dir = init::version_data_dir(&version);
+                init::open_data_dir(&data_dir, &version, allow_downgrade).map_err(|e| match e {
+                    Error::DataTooNew { .. } => {
+                        Error::Server(format!("{}; pass --allow-downgrade to open it anyway", e))
+                    }
+                    e => e,
+                })?;
+                cmd.current_dir(&data_dir);
+                init::push_overrides(&mut server_args, init::server_flags());
+            }
+            if keeper {
+                keeper::ensure_running(&version)?;
+                init::push_overrides(&mut server_args, keeper::server_flags());
             }
+            cmd.args(&server_args);
             let err = cmd.exec();
             Err(Error::Exec(err.to_string()))
         }
         Some(RunCommands::Client { args }) => {
             let mut cmd = Command::new(&binary);
             cmd.arg("client").args(&args);
+            if let Some(secret) = cloud::secrets::client_secret(&args) {
+                cmd.env("CLICKHOUSE_PASSWORD", secret.password)
+                    .args(secret.extra_args);
+            }
             let err = cmd.exec();
             Err(Error::Exec(err.to_string()))
         }
@@ -206,13 +403,36 @@ fn run_clickhouse(args: RunArgs) -> Result<()> {
     }
 }
 
+fn run_keeper(command: KeeperCommands) -> Result<()> {
+    match command {
+        KeeperCommands::Start => {
+            let version = version_manager::get_default_version()?;
+            let pid = keeper::start(&version)?;
+            println!(
+                "Started ClickHouse Keeper {} on port {} (pid {})",
+                version,
+                keeper::KEEPER_PORT,
+                pid
+            );
+            Ok(())
+        }
+        KeeperCommands::Stop => keeper::stop(),
+        KeeperCommands::Status => keeper::status(),
+    }
+}
+
 async fn run_cloud(args: CloudArgs) -> Result<()> {
-    if let CloudCommands::Auth = &args.command {
+    if let CloudCommands::Auth { command: None } = &args.command {
         return cloud::commands::auth_interactive().map_err(|e| Error::Cloud(e.to_string()));
     }
 
-    let client = CloudClient::new(args.api_key.as_deref(), args.api_secret.as_deref())
-        .map_err(|e| Error::Cloud(e.to_string()))?;
+    let options = cloud::client::ClientOptions {
+        base_url: args.api_url,
+        connect_timeout: Duration::from_secs(args.connect_timeout),
+        request_timeout: Duration::from_secs(args.request_timeout),
+        max_retries: args.max_retries,
+    };
+    let client = CloudClient::new(args.api_key.as_deref(), args.api_secret.as_deref(), options)?;
 
     let json = args.json;
 
@@ -220,6 +440,9 @@ async fn run_cloud(args: CloudArgs) -> Result<()> {
         CloudCommands::Org { command } => match command {
             OrgCommands::List => cloud::commands::org_list(&client, json).await,
             OrgCommands::Get { org_id } => cloud::commands::org_get(&client, &org_id, json).await,
+            OrgCommands::Use { org, global } => {
+                cloud::commands::org_use(&client, &org, global).await
+            }
         },
         CloudCommands::Service { command } => match command {
             ServiceCommands::List { org_id } => {
@@ -249,6 +472,8 @@ async fn run_cloud(args: CloudArgs) -> Result<()> {
                 compliance_type,
                 profile,
                 org_id,
+                wait,
+                timeout,
             } => {
                 let opts = cloud::commands::CreateServiceOptions {
                     name,
@@ -272,19 +497,176 @@ async fn run_cloud(args: CloudArgs) -> Result<()> {
                     profile,
                     org_id,
                 };
-                cloud::commands::service_create(&client, opts, json).await
+                let wait = wait.then(|| Duration::from_secs(timeout));
+                cloud::commands::service_create(&client, opts, wait, json).await
+            }
+            ServiceCommands::Update {
+                service_id,
+                org_id,
+                name,
+                release_channel,
+                idle_scaling,
+                idle_timeout_minutes,
+                add_ip,
+                remove_ip,
+                add_tag,
+                remove_tag,
+            } => {
+                let opts = cloud::commands::UpdateServiceOptions {
+                    name,
+                    release_channel,
+                    idle_scaling,
+                    idle_timeout_minutes,
+                    add_ip,
+                    remove_ip,
+                    add_tag,
+                    remove_tag,
+                };
+                cloud::commands::service_update(&client, &service_id, org_id.as_deref(), opts, json)
+                    .await
             }
-            ServiceCommands::Delete { service_id, org_id } => {
-                cloud::commands::service_delete(&client, &service_id, org_id.as_deref()).await
+            ServiceCommands::Scale {
+                service_id,
+                org_id,
+                min_replica_memory_gb,
+                max_replica_memory_gb,
+                num_replicas,
+                wait,
+                timeout,
+            } => {
+                let request = cloud::types::ReplicaScalingRequest {
+                    min_replica_memory_gb,
+                    max_replica_memory_gb,
+                    num_replicas,
+                    ..Default::default()
+                };
+                let wait = wait.then(|| Duration::from_secs(timeout));
+                cloud::commands::service_scale(
+                    &client,
+                    &service_id,
+                    org_id.as_deref(),
+                    request,
+                    wait,
+                    json,
+                )
+                .await
             }
-            ServiceCommands::Start { service_id, org_id } => {
-                cloud::commands::service_start(&client, &service_id, org_id.as_deref(), json).await
+            ServiceCommands::Ip { command } => match command {
+                IpCommands::List { service_id, org_id } => {
+                    cloud::commands::ip_list(&client, &service_id, org_id.as_deref(), json).await
+                }
+                IpCommands::Add {
+                    service_id,
+                    sources,
+                    org_id,
+                    description,
+                    add_my_ip,
+                    my_ip_url,
+                } => {
+                    let opts = cloud::commands::IpEntriesOptions {
+                        sources,
+                        description,
+                        add_my_ip,
+                        my_ip_url,
+                    };
+                    let change = cloud::commands::IpChange::Add(opts);
+                    cloud::commands::ip_update(
+                        &client,
+                        &service_id,
+                        org_id.as_deref(),
+                        change,
+                        json,
+                    )
+                    .await
+                }
+                IpCommands::Remove {
+                    service_id,
+                    sources,
+                    org_id,
+                } => {
+                    let change = cloud::commands::IpChange::Remove(sources);
+                    cloud::commands::ip_update(
+                        &client,
+                        &service_id,
+                        org_id.as_deref(),
+                        change,
+                        json,
+                    )
+                    .await
+                }
+                IpCommands::Set {
+                    service_id,
+                    sources,
+                    org_id,
+                    description,
+                    add_my_ip,
+                    my_ip_url,
+                } => {
+                    let opts = cloud::commands::IpEntriesOptions {
+                        sources,
+                        description,
+                        add_my_ip,
+                        my_ip_url,
+                    };
+                    let change = cloud::commands::IpChange::Set(opts);
+                    cloud::commands::ip_update(
+                        &client,
+                        &service_id,
+                        org_id.as_deref(),
+                        change,
+                        json,
+                    )
+                    .await
+                }
+            },
+            ServiceCommands::ResetPassword {
+                service_id,
+                org_id,
+                save,
+            } => {
+                cloud::commands::service_reset_password(
+                    &client,
+                    &service_id,
+                    org_id.as_deref(),
+                    save,
+                    json,
+                )
+                .await
             }
-            ServiceCommands::Stop { service_id, org_id } => {
-                cloud::commands::service_stop(&client, &service_id, org_id.as_deref(), json).await
+            ServiceCommands::Delete {
+                service_id,
+                org_id,
+                wait,
+                timeout,
+            } => {
+                let wait = wait.then(|| Duration::from_secs(timeout));
+                cloud::commands::service_delete(&client, &service_id, org_id.as_deref(), wait).await
+            }
+            ServiceCommands::Start {
+                service_id,
+                org_id,
+                wait,
+                timeout,
+            } => {
+                let wait = wait.then(|| Duration::from_secs(timeout));
+                cloud::commands::service_start(&client, &service_id, org_id.as_deref(), wait, json)
+                    .await
             }
+            ServiceCommands::Stop {
+                service_id,
+                org_id,
+                wait,
+                timeout,
+            } => {
+                let wait = wait.then(|| Duration::from_secs(timeout));
+                cloud::commands::service_stop(&client, &service_id, org_id.as_deref(), wait, json)
+                    .await
+            }
+        },
+        CloudCommands::Auth { command } => match command {
+            Some(AuthCommands::Status) => cloud::commands::auth_status(&client, json).await,
+            None => unreachable!("handled above"),
         },
-        CloudCommands::Auth => unreachable!("handled above"),
         CloudCommands::Backup { command } => match command {
             BackupCommands::List { service_id, org_id } => {
                 cloud::commands::backup_list(&client, &service_id, org_id.as_deref(), json).await
@@ -306,5 +688,8 @@ async fn run_cloud(args: CloudArgs) -> Result<()> {
         },
     };
 
-    result.map_err(|e| Error::Cloud(e.to_string()))
+    result.map_err(|e| match e.downcast::<cloud::client::CloudError>() {
+        Ok(e) => Error::from(*e),
+        Err(e) => Error::Cloud(e.to_string()),
+    })
 }
@@ -0,0 +1,372 @@
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::init;
+use crate::sql;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Table inside the target server that records applied migrations
+pub const MIGRATIONS_TABLE: &str = "chv_migrations";
+
+/// Returns the migrations directory (clickhouse/migrations/)
+pub fn migrations_dir() -> PathBuf {
+    init::project_dir().join("migrations")
+}
+
+/// A migration on disk: `<version>_<name>.up.sql` with an optional `.down.sql`
+struct Migration {
+    version: String,
+    name: String,
+    up: PathBuf,
+    down: PathBuf,
+}
+
+impl Migration {
+    fn file_name(&self) -> String {
+        format!("{}_{}", self.version, self.name)
+    }
+}
+
+/// A migration recorded as applied in the tracking table
+struct Applied {
+    name: String,
+    checksum: String,
+    applied_at: String,
+}
+
+/// FNV-1a 64-bit hash of a migration's up script, used to detect edits after apply
+fn checksum(text: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in text.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Turns a free-form name into a file-name-safe slug
+fn slugify(name: &str) -> String {
+    let slug: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    slug.split('_')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Loads migrations from disk, sorted by version
+fn load_migrations() -> Result<Vec<Migration>> {
+    load_migrations_from(&migrations_dir())
+}
+
+/// Reads the migrations in `dir`, ordered by version. Versions must be unique, since the
+/// tracking table keys applied migrations by version alone.
+fn load_migrations_from(dir: &Path) -> Result<Vec<Migration>> {
+    let mut migrations: Vec<Migration> = Vec::new();
+    if !dir.exists() {
+        return Ok(migrations);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(file) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(stem) = file.strip_suffix(".up.sql") else {
+            continue;
+        };
+        let Some((version, name)) = stem.split_once('_') else {
+            continue;
+        };
+        if version.is_empty() || !version.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        migrations.push(Migration {
+            version: version.to_string(),
+            name: name.to_string(),
+            down: dir.join(format!("{}.down.sql", stem)),
+            up: path,
+        });
+    }
+    migrations.sort_by(|a, b| a.version.cmp(&b.version).then(a.up.cmp(&b.up)));
+    if let Some(pair) = migrations.windows(2).find(|w| w[0].version == w[1].version) {
+        return Err(Error::Migration(format!(
+            "{} and {} have the same version {}; rename one of them",
+            pair[0].up.display(),
+            pair[1].up.display(),
+            pair[0].version
+        )));
+    }
+    Ok(migrations)
+}
+
+fn ensure_table(client: &Client) -> Result<()> {
+    client.query(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (\
+         version String, \
+         name String, \
+         checksum String, \
+         applied UInt8, \
+         applied_at DateTime64(3) DEFAULT now64(3)\
+         ) ENGINE = MergeTree ORDER BY (version, applied_at)",
+        MIGRATIONS_TABLE
+    ))?;
+    Ok(())
+}
+
+/// Reads currently applied migrations. The table is append-only: each up or down
+/// adds a row, and the latest row per version decides whether it is applied.
+fn load_applied(client: &Client) -> Result<BTreeMap<String, Applied>> {
+    let output = client.query(&format!(
+        "SELECT version, argMax(name, applied_at), argMax(checksum, applied_at), max(applied_at) \
+         FROM {} GROUP BY version HAVING argMax(applied, applied_at) = 1 ORDER BY version",
+        MIGRATIONS_TABLE
+    ))?;
+    let mut applied = BTreeMap::new();
+    for line in output.lines() {
+        let cols: Vec<&str> = line.split('\t').collect();
+        if let [version, name, checksum, applied_at] = cols[..] {
+            applied.insert(
+                version.to_string(),
+                Applied {
+                    name: name.to_string(),
+                    checksum: checksum.to_string(),
+                    applied_at: applied_at.to_string(),
+                },
+            );
+        }
+    }
+    Ok(applied)
+}
+
+fn record(client: &Client, m: &Migration, sum: &str, applied: bool) -> Result<()> {
+    client.query(&format!(
+        "INSERT INTO {} (version, name, checksum, applied) VALUES ('{}', '{}', '{}', {})",
+        MIGRATIONS_TABLE,
+        m.version,
+        m.name.replace('\\', "\\\\").replace('\'', "\\'"),
+        sum,
+        applied as u8
+    ))?;
+    Ok(())
+}
+
+/// Creates a new pair of timestamped up/down migration files
+pub fn new(name: &str) -> Result<()> {
+    let slug = slugify(name);
+    if slug.is_empty() {
+        return Err(Error::Migration(format!(
+            "Invalid migration name: {}",
+            name
+        )));
+    }
+
+    let dir = migrations_dir();
+    std::fs::create_dir_all(&dir)?;
+    let version = chrono::Utc::now().format("%Y%m%d%H%M%S").to_string();
+    let up = dir.join(format!("{}_{}.up.sql", version, slug));
+    let down = dir.join(format!("{}_{}.down.sql", version, slug));
+    if up.exists() {
+        return Err(Error::Migration(format!("{} already exists", up.display())));
+    }
+
+    std::fs::write(
+        &up,
+        format!("-- Migration: {}\n-- Applied by `chv migrate up`\n\n", slug),
+    )?;
+    std::fs::write(
+        &down,
+        format!(
+            "-- Migration: {}\n-- Reverts the up migration; run by `chv migrate down`\n\n",
+            slug
+        ),
+    )?;
+
+    println!("Created {}", up.display());
+    println!("Created {}", down.display());
+    Ok(())
+}
+
+/// Returns applied migrations whose up script changed since they were applied
+fn changed<'a>(
+    migrations: &'a [Migration],
+    applied: &BTreeMap<String, Applied>,
+) -> Result<Vec<&'a Migration>> {
+    let mut changed = Vec::new();
+    for m in migrations {
+        if let Some(a) = applied.get(&m.version)
+            && checksum(&std::fs::read_to_string(&m.up)?) != a.checksum
+        {
+            changed.push(m);
+        }
+    }
+    Ok(changed)
+}
+
+/// Applies pending migrations in version order, optionally stopping at `target`
+pub fn up(client: &Client, target: Option<&str>) -> Result<()> {
+    ensure_table(client)?;
+    let migrations = load_migrations()?;
+    let applied = load_applied(client)?;
+
+    let edited = changed(&migrations, &applied)?;
+    if !edited.is_empty() {
+        let names: Vec<String> = edited.iter().map(|m| m.file_name()).collect();
+        return Err(Error::Migration(format!(
+            "Applied migrations were edited after being applied: {}. \
+             Revert the edits or add a new migration instead",
+            names.join(", ")
+        )));
+    }
+
+    let pending: Vec<&Migration> = migrations
+        .iter()
+        .filter(|m| !applied.contains_key(&m.version))
+        .filter(|m| target.is_none_or(|t| m.version.as_str() <= t))
+        .collect();
+
+    if pending.is_empty() {
+        println!("No pending migrations");
+        return Ok(());
+    }
+
+    println!(
+        "Applying {} migrations to {}",
+        pending.len(),
+        client.endpoint()
+    );
+    for m in pending {
+        let text = std::fs::read_to_string(&m.up)?;
+        let rel = format!("migrations/{}.up.sql", m.file_name());
+        if let Err(e) = client.execute_file(&rel, &text, &sql::split_statements(&text)) {
+            println!("  failed  {}", m.file_name());
+            return Err(e);
+        }
+        record(client, m, &checksum(&text), true)?;
+        println!("  applied {}", m.file_name());
+    }
+    Ok(())
+}
+
+/// Reverts the most recently applied `steps` migrations
+pub fn down(client: &Client, steps: usize) -> Result<()> {
+    ensure_table(client)?;
+    let migrations = load_migrations()?;
+    let applied = load_applied(client)?;
+
+    let to_revert: Vec<&String> = applied.keys().rev().take(steps).collect();
+    if to_revert.is_empty() {
+        println!("No applied migrations");
+        return Ok(());
+    }
+
+    for version in to_revert {
+        let Some(m) = migrations.iter().find(|m| &m.version == version) else {
+            return Err(Error::Migration(format!(
+                "Migration {}_{} is applied but its files are missing from {}",
+                version,
+                applied[version].name,
+                migrations_dir().display()
+            )));
+        };
+        let text = std::fs::read_to_string(&m.down).unwrap_or_default();
+        let statements = sql::split_statements(&text);
+        if statements.is_empty() {
+            return Err(Error::Migration(format!(
+                "{} has no statements; cannot revert {}",
+                m.down.display(),
+                m.file_name()
+            )));
+        }
+        let rel = format!("migrations/{}.down.sql", m.file_name());
+        if let Err(e) = client.execute_file(&rel, &text, &statements) {
+            println!("  failed   {}", m.file_name());
+            return Err(e);
+        }
+        record(client, m, &applied[version].checksum, false)?;
+        println!("  reverted {}", m.file_name());
+    }
+    Ok(())
+}
+
+/// Prints each migration's state: applied, pending, changed, or missing
+pub fn status(client: &Client) -> Result<()> {
+    ensure_table(client)?;
+    let migrations = load_migrations()?;
+    let applied = load_applied(client)?;
+    let edited: Vec<String> = changed(&migrations, &applied)?
+        .iter()
+        .map(|m| m.version.clone())
+        .collect();
+
+    if migrations.is_empty() && applied.is_empty() {
+        println!("No migrations found in {}", migrations_dir().display());
+        println!("Run: chv migrate new <name>");
+        return Ok(());
+    }
+
+    println!("Migrations ({}):", client.endpoint());
+    for m in &migrations {
+        match applied.get(&m.version) {
+            Some(a) if edited.contains(&m.version) => {
+                println!(
+                    "  changed  {} (applied {}, edited since)",
+                    m.file_name(),
+                    a.applied_at
+                )
+            }
+            Some(a) => println!("  applied  {} ({})", m.file_name(), a.applied_at),
+            None => println!("  pending  {}", m.file_name()),
+        }
+    }
+    for (version, a) in &applied {
+        if !migrations.iter().any(|m| &m.version == version) {
+            println!(
+                "  missing  {}_{} (applied {}, file not found)",
+                version, a.name, a.applied_at
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Add users table"), "add_users_table");
+        assert_eq!(slugify("  drop--old  "), "drop_old");
+        assert_eq!(slugify("!!"), "");
+    }
+
+    #[test]
+    fn test_load_migrations_rejects_duplicate_versions() {
+        let dir = std::env::temp_dir().join(format!("chv-migrate-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("20250101000000_add_users.up.sql"), "").unwrap();
+        std::fs::write(dir.join("20250102000000_add_orders.up.sql"), "").unwrap();
+        std::fs::write(dir.join("20250102000000_add_orders.down.sql"), "").unwrap();
+        std::fs::write(dir.join("notes.md"), "").unwrap();
+        let migrations = load_migrations_from(&dir).unwrap();
+        let names: Vec<&str> = migrations.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["add_users", "add_orders"]);
+
+        std::fs::write(dir.join("20250102000000_add_events.up.sql"), "").unwrap();
+        let err = load_migrations_from(&dir).err().unwrap().to_string();
+        assert!(err.contains("20250102000000_add_events.up.sql"), "{}", err);
+        assert!(err.contains("20250102000000_add_orders.up.sql"), "{}", err);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_checksum_is_stable() {
+        assert_eq!(checksum(""), "cbf29ce484222325");
+        assert_ne!(checksum("CREATE TABLE a"), checksum("CREATE TABLE b"));
+    }
+}
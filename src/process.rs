@@ -0,0 +1,90 @@
+use crate::error::Result;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Reads a PID from a pid file, returning None if the file is missing or malformed
+pub fn read_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Writes a PID to a pid file
+pub fn write_pid(path: &Path, pid: u32) -> Result<()> {
+    std::fs::write(path, format!("{}\n", pid))?;
+    Ok(())
+}
+
+/// Returns true if a process with the given PID is alive
+pub fn is_running(pid: u32) -> bool {
+    Command::new("kill")
+        .arg("-0")
+        .arg(pid.to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+/// Returns the command line of a running process, with arguments separated by spaces.
+/// Reads /proc where available and falls back to `ps`.
+pub fn command_line(pid: u32) -> Option<String> {
+    if let Ok(raw) = std::fs::read(format!("/proc/{}/cmdline", pid)) {
+        let args: Vec<String> = raw
+            .split(|b| *b == 0)
+            .filter(|a| !a.is_empty())
+            .map(|a| String::from_utf8_lossy(a).into_owned())
+            .collect();
+        return (!args.is_empty()).then(|| args.join(" "));
+    }
+    let output = Command::new("ps")
+        .args(["-o", "command=", "-p", &pid.to_string()])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !line.is_empty()).then_some(line)
+}
+
+/// Returns the PID recorded in a pid file if that process is still alive
+pub fn running_pid(pid_file: &Path) -> Option<u32> {
+    read_pid(pid_file).filter(|pid| is_running(*pid))
+}
+
+/// Sends SIGTERM to a process
+pub fn signal_term(pid: u32) {
+    let _ = Command::new("kill")
+        .arg("-TERM")
+        .arg(pid.to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Sends SIGTERM to a process and waits up to `timeout` for it to exit.
+/// Returns true if the process exited.
+pub fn terminate(pid: u32, timeout: Duration) -> bool {
+    signal_term(pid);
+
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if !is_running(pid) {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+    !is_running(pid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_str().unwrap();
+        let cmd = command_line(std::process::id()).unwrap();
+        assert!(cmd.contains(name), "{}", cmd);
+    }
+}
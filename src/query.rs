@@ -0,0 +1,193 @@
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::init;
+use crate::paths;
+use crate::sql::{self, QueryParam};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Returns the saved queries directory (clickhouse/queries/)
+pub fn queries_dir() -> PathBuf {
+    init::project_dir().join("queries")
+}
+
+/// A saved query file
+struct SavedQuery {
+    /// Path relative to queries/ without the .sql extension
+    name: String,
+    /// First line comment, if the file starts with one
+    description: Option<String>,
+    params: Vec<QueryParam>,
+}
+
+impl SavedQuery {
+    fn load(name: String, path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let description = text
+            .lines()
+            .next()
+            .and_then(|l| l.trim().strip_prefix("--"))
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty());
+        Ok(Self {
+            name,
+            description,
+            params: sql::query_params(&text),
+        })
+    }
+}
+
+/// Collects saved queries below `dir`, including subdirectories, sorted by name
+fn collect(dir: &Path, prefix: &str, queries: &mut Vec<SavedQuery>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if path.is_dir() {
+            collect(&path, &format!("{}{}/", prefix, file_name), queries)?;
+        } else if let Some(stem) = file_name.strip_suffix(".sql") {
+            queries.push(SavedQuery::load(format!("{}{}", prefix, stem), &path)?);
+        }
+    }
+    Ok(())
+}
+
+/// Resolves a saved query name (`daily_users` or `reports/daily_users`) to its file
+fn find(name: &str) -> Result<PathBuf> {
+    let rel = name.strip_suffix(".sql").unwrap_or(name);
+    let path = queries_dir().join(format!("{}.sql", rel));
+    if rel.split('/').any(|part| part == ".." || part.is_empty()) || !path.is_file() {
+        return Err(Error::QueryNotFound(name.to_string()));
+    }
+    Ok(path)
+}
+
+/// Parses `key=value` arguments and checks them against the query's declared parameters
+fn bind_params(declared: &[QueryParam], args: &[String]) -> Result<Vec<String>> {
+    let mut flags = Vec::new();
+    let mut given = Vec::new();
+    for arg in args {
+        let Some((key, value)) = arg.split_once('=') else {
+            return Err(Error::Query(format!(
+                "Invalid --param {} (expected key=value)",
+                arg
+            )));
+        };
+        if !declared.iter().any(|p| p.name == key) {
+            return Err(Error::Query(format!(
+                "Unknown parameter {}. Declared: {}",
+                key,
+                describe(declared)
+            )));
+        }
+        given.push(key);
+        flags.push(format!("--param_{}={}", key, value));
+    }
+
+    let missing: Vec<String> = declared
+        .iter()
+        .filter(|p| !given.contains(&p.name.as_str()))
+        .map(|p| format!("--param {}=<{}>", p.name, p.ty))
+        .collect();
+    if !missing.is_empty() {
+        return Err(Error::Query(format!(
+            "Missing parameters: {}",
+            missing.join(" ")
+        )));
+    }
+    Ok(flags)
+}
+
+fn describe(params: &[QueryParam]) -> String {
+    if params.is_empty() {
+        return "none".to_string();
+    }
+    params
+        .iter()
+        .map(|p| format!("{}:{}", p.name, p.ty))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Lists saved queries with their declared parameters
+pub fn list() -> Result<()> {
+    let dir = queries_dir();
+    let mut queries = Vec::new();
+    if dir.exists() {
+        collect(&dir, "", &mut queries)?;
+    }
+    if queries.is_empty() {
+        println!("No saved queries found in {}", dir.display());
+        return Ok(());
+    }
+
+    queries.sort_by(|a, b| a.name.cmp(&b.name));
+    println!("Saved queries:");
+    for q in queries {
+        let params = if q.params.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", describe(&q.params))
+        };
+        match q.description {
+            Some(d) => println!("  {}{} - {}", q.name, params, d),
+            None => println!("  {}{}", q.name, params),
+        }
+    }
+    Ok(())
+}
+
+/// Runs a saved query with bound parameters, handing the terminal to clickhouse-client
+/// (or clickhouse-local when `client` is None)
+pub fn run(
+    version: &str,
+    client: Option<&Client>,
+    name: &str,
+    params: &[String],
+    format: &str,
+) -> Result<()> {
+    let path = find(name)?;
+    let text = std::fs::read_to_string(&path)?;
+    let flags = bind_params(&sql::query_params(&text), params)?;
+
+    let mut cmd = match client {
+        Some(client) => client.command(),
+        None => {
+            let binary = paths::binary_path(version)?;
+            if !binary.exists() {
+                return Err(Error::VersionNotFound(version.to_string()));
+            }
+            let mut cmd = Command::new(binary);
+            cmd.arg("local");
+            cmd
+        }
+    };
+    cmd.arg(format!("--format={}", format))
+        .args(&flags)
+        .arg("--query")
+        .arg(text.trim());
+
+    let err = cmd.exec();
+    Err(Error::Exec(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind_params() {
+        let declared = sql::query_params("SELECT {day:Date}, {limit:UInt32}");
+        let flags = bind_params(
+            &declared,
+            &["limit=10".to_string(), "day=2024-01-01".to_string()],
+        )
+        .unwrap();
+        assert_eq!(flags, vec!["--param_limit=10", "--param_day=2024-01-01"]);
+
+        assert!(bind_params(&declared, &["day=2024-01-01".to_string()]).is_err());
+        assert!(bind_params(&declared, &["nope".to_string()]).is_err());
+    }
+}
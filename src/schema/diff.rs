@@ -0,0 +1,374 @@
+use super::{SYSTEM_DATABASES, load_project_files};
+use crate::client::{self, Client};
+use crate::error::{Error, Result};
+use crate::sql::{self, ObjectKind, ObjectName, Token};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// The server-side default added to MergeTree tables that don't set it
+const DEFAULT_GRANULARITY: &str = "index_granularity = 8192";
+
+/// A table, view or dictionary reduced to comparable parts
+#[derive(Debug)]
+struct ObjectDef {
+    kind: ObjectKind,
+    name: ObjectName,
+    /// Column name → type and modifiers, in definition order
+    columns: Vec<(String, String)>,
+    /// Everything after the column list: TO/AS clauses, engine, keys, settings, query
+    body: String,
+}
+
+/// A difference found for a single object
+enum Change {
+    OnlyInFiles(ObjectDef),
+    OnlyOnServer(ObjectDef),
+    Changed {
+        kind: ObjectKind,
+        name: ObjectName,
+        details: Vec<String>,
+    },
+}
+
+/// Splits a CREATE statement into columns and body, qualifying names with `default_db`
+fn describe(ddl: &str, default_db: &str) -> Option<ObjectDef> {
+    let (info, tokens) = sql::parse_create_parts(ddl)?;
+    if info.kind == ObjectKind::Database {
+        return None;
+    }
+    let name = qualify(info.name, default_db);
+
+    let mut rest: &[Token] = &tokens;
+    let mut columns = Vec::new();
+    let mut extra: Vec<String> = Vec::new();
+    if info.kind == ObjectKind::Table
+        && rest.first().is_some_and(|t| t.is_punct("("))
+        && let Some(end) = sql::matching_paren(rest)
+    {
+        for element in sql::split_top_level(&rest[1..end]) {
+            let Some(first) = element.first() else {
+                continue;
+            };
+            if ["INDEX", "PROJECTION", "CONSTRAINT", "PRIMARY"]
+                .iter()
+                .any(|kw| first.is_kw(kw))
+            {
+                extra.push(sql::render(element));
+            } else {
+                columns.push((first.text.clone(), sql::render(&element[1..])));
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+
+    let mut body_tokens: Vec<Token> = rest.to_vec();
+    if matches!(info.kind, ObjectKind::View | ObjectKind::MaterializedView) {
+        qualify_view(&mut body_tokens, default_db);
+    }
+
+    extra.push(normalize_body(&sql::render(&body_tokens)));
+    Some(ObjectDef {
+        kind: info.kind,
+        name,
+        columns,
+        body: extra.join(", "),
+    })
+}
+
+/// Brings a view body into the shape the server stores: `TO` target and tables read
+/// with FROM/JOIN qualified, and the server-derived column list removed
+fn qualify_view(tokens: &mut Vec<Token>, default_db: &str) {
+    let mut i = 0;
+    if tokens.first().is_some_and(|t| t.is_kw("TO"))
+        && let Some((target, next)) = sql::parse_name(tokens, 1)
+    {
+        let target = qualify(target, default_db);
+        let qualified = sql::tokenize(&format!("TO {}", target.quoted()));
+        i = qualified.len();
+        tokens.splice(0..next, qualified);
+    }
+    if tokens.get(i).is_some_and(|t| t.is_punct("("))
+        && let Some(end) = sql::matching_paren(&tokens[i..])
+    {
+        tokens.drain(i..i + end + 1);
+    }
+
+    let mut j = 0;
+    while j + 1 < tokens.len() {
+        if (tokens[j].is_kw("FROM") || tokens[j].is_kw("JOIN"))
+            && !tokens[j + 1].is_kw("SELECT")
+            && !tokens[j + 1].is_kw("WITH")
+            && let Some((source, next)) = sql::parse_name(tokens, j + 1)
+            && source.database.is_none()
+            && !tokens.get(next).is_some_and(|t| t.is_punct("("))
+        {
+            let qualified = sql::tokenize(&qualify(source, default_db).quoted());
+            tokens.splice(j + 1..next, qualified);
+        }
+        j += 1;
+    }
+}
+
+fn qualify(name: ObjectName, default_db: &str) -> ObjectName {
+    ObjectName {
+        database: Some(name.database.unwrap_or_else(|| default_db.to_string())),
+        name: name.name,
+    }
+}
+
+/// Drops the server's default settings and the empty parentheses of `ENGINE = MergeTree()`
+fn normalize_body(body: &str) -> String {
+    let body = body
+        .replace(&format!(" SETTINGS {}", DEFAULT_GRANULARITY), "")
+        .replace(&format!(", {}", DEFAULT_GRANULARITY), "")
+        .replace(&format!("{}, ", DEFAULT_GRANULARITY), "");
+    let mut tokens = sql::tokenize(&body);
+    if let Some(i) = tokens.iter().position(|t| t.is_kw("ENGINE"))
+        && tokens.get(i + 1).is_some_and(|t| t.is_punct("="))
+        && tokens.get(i + 3).is_some_and(|t| t.is_punct("("))
+        && tokens.get(i + 4).is_some_and(|t| t.is_punct(")"))
+    {
+        tokens.drain(i + 3..i + 5);
+    }
+    sql::render(&tokens)
+}
+
+/// Compares two definitions of the same object, returning human-readable differences
+fn compare(file: &ObjectDef, server: &ObjectDef) -> Vec<String> {
+    let mut details = Vec::new();
+    if file.kind != server.kind {
+        details.push(format!("kind: {} → {}", server.kind, file.kind));
+        return details;
+    }
+
+    let server_cols: BTreeMap<&str, &str> = server
+        .columns
+        .iter()
+        .map(|(n, d)| (n.as_str(), d.as_str()))
+        .collect();
+    let file_cols: BTreeMap<&str, &str> = file
+        .columns
+        .iter()
+        .map(|(n, d)| (n.as_str(), d.as_str()))
+        .collect();
+
+    for (name, def) in &file.columns {
+        match server_cols.get(name.as_str()) {
+            None => details.push(format!("+ column {} {}", name, def)),
+            Some(server_def) if server_def != def => {
+                details.push(format!("~ column {}: {} → {}", name, server_def, def))
+            }
+            _ => {}
+        }
+    }
+    for (name, def) in &server.columns {
+        if !file_cols.contains_key(name.as_str()) {
+            details.push(format!("- column {} {}", name, def));
+        }
+    }
+
+    if file.body != server.body {
+        details.push(format!("- {}", server.body));
+        details.push(format!("+ {}", file.body));
+    }
+    details
+}
+
+/// Loads the objects defined in project files, formatted through `clickhouse format`
+fn file_objects(version: &str, default_db: &str) -> Result<Vec<ObjectDef>> {
+    let mut objects = Vec::new();
+    for file in load_project_files()? {
+        for stmt in &file.statements {
+            if sql::parse_create(&stmt.sql).is_none() {
+                continue;
+            }
+            let formatted =
+                client::format_sql(version, &stmt.sql, &["--oneline"]).map_err(|e| {
+                    let message = match e {
+                        Error::Query(m) => m,
+                        other => other.to_string(),
+                    };
+                    Error::Schema(format!(
+                        "{}:{}: {}",
+                        file.rel,
+                        sql::error_line(stmt, &message),
+                        message
+                    ))
+                })?;
+            if let Some(def) = describe(&formatted, default_db) {
+                objects.push(def);
+            }
+        }
+    }
+    Ok(objects)
+}
+
+#[derive(Deserialize)]
+struct ServerTable {
+    database: String,
+    name: String,
+    create_table_query: String,
+}
+
+/// Loads the objects in the given databases from system.tables
+fn server_objects(client: &Client, version: &str, databases: &[String]) -> Result<Vec<ObjectDef>> {
+    let list: Vec<String> = databases
+        .iter()
+        .filter(|d| !SYSTEM_DATABASES.contains(&d.as_str()))
+        .map(|d| format!("'{}'", d.replace('\\', "\\\\").replace('\'', "\\'")))
+        .collect();
+    if list.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let output = client.query_with(
+        &format!(
+            "SELECT database, name, create_table_query FROM system.tables \
+             WHERE database IN ({}) AND NOT is_temporary AND name NOT LIKE '.inner%' \
+             AND name != '{}' ORDER BY database, name",
+            list.join(", "),
+            crate::migrate::MIGRATIONS_TABLE
+        ),
+        &["--format=JSONEachRow".to_string()],
+    )?;
+
+    let mut objects = Vec::new();
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        let table: ServerTable = serde_json::from_str(line)?;
+        let formatted = client::format_sql(version, &table.create_table_query, &["--oneline"])
+            .unwrap_or(table.create_table_query);
+        if let Some(def) = describe(&formatted, &table.database) {
+            objects.push(def);
+        } else {
+            eprintln!(
+                "Warning: could not parse the definition of {}.{}",
+                table.database, table.name
+            );
+        }
+    }
+    Ok(objects)
+}
+
+/// Compares project files with the objects on a server and prints the differences.
+/// Returns an error when they differ, so the command exits non-zero.
+pub fn diff(client: &Client, version: &str) -> Result<()> {
+    let default_db = client.query("SELECT currentDatabase()")?.trim().to_string();
+    let files = file_objects(version, &default_db)?;
+
+    let mut databases: Vec<String> = vec![default_db.clone()];
+    for obj in &files {
+        if let Some(db) = &obj.name.database
+            && !databases.contains(db)
+        {
+            databases.push(db.clone());
+        }
+    }
+    let mut server: Vec<Option<ObjectDef>> = server_objects(client, version, &databases)?
+        .into_iter()
+        .map(Some)
+        .collect();
+
+    let mut changes = Vec::new();
+    for file in files {
+        let found = server
+            .iter_mut()
+            .find(|s| s.as_ref().is_some_and(|s| s.name == file.name))
+            .and_then(Option::take);
+        match found {
+            None => changes.push(Change::OnlyInFiles(file)),
+            Some(server_def) => {
+                let details = compare(&file, &server_def);
+                if !details.is_empty() {
+                    changes.push(Change::Changed {
+                        kind: file.kind,
+                        name: file.name,
+                        details,
+                    });
+                }
+            }
+        }
+    }
+    changes.extend(server.into_iter().flatten().map(Change::OnlyOnServer));
+
+    if changes.is_empty() {
+        println!(
+            "No differences between project files and {} ({})",
+            client.endpoint(),
+            databases.join(", ")
+        );
+        return Ok(());
+    }
+
+    println!("Schema diff (project files vs {}):", client.endpoint());
+    for change in &changes {
+        match change {
+            Change::OnlyInFiles(def) => println!("  + {} {} (only in files)", def.kind, def.name),
+            Change::OnlyOnServer(def) => {
+                println!("  - {} {} (only on server)", def.kind, def.name)
+            }
+            Change::Changed {
+                kind,
+                name,
+                details,
+            } => {
+                println!("  ~ {} {}", kind, name);
+                for d in details {
+                    println!("      {}", d);
+                }
+            }
+        }
+    }
+
+    Err(Error::Schema(format!(
+        "{} objects differ between project files and {}",
+        changes.len(),
+        client.endpoint()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_columns_and_engine() {
+        let file = describe(
+            "CREATE TABLE IF NOT EXISTS events (id UInt64, country LowCardinality(String), \
+             ts DateTime DEFAULT now()) ENGINE = MergeTree() ORDER BY (id, ts)",
+            "default",
+        )
+        .unwrap();
+        let server = describe(
+            "CREATE TABLE default.events (`id` UInt32, `ts` DateTime DEFAULT now(), \
+             `legacy` String) ENGINE = MergeTree ORDER BY (id, ts) SETTINGS index_granularity = 8192",
+            "default",
+        )
+        .unwrap();
+        assert_eq!(file.name, server.name);
+        assert_eq!(file.body, server.body);
+        assert_eq!(
+            compare(&file, &server),
+            vec![
+                "~ column id: UInt32 → UInt64",
+                "+ column country LowCardinality(String)",
+                "- column legacy String",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mv_names_are_qualified() {
+        let file = describe(
+            "CREATE MATERIALIZED VIEW mv TO daily AS SELECT id FROM events e JOIN (SELECT 1) USING id",
+            "default",
+        )
+        .unwrap();
+        let server = describe(
+            "CREATE MATERIALIZED VIEW default.mv TO default.daily (`id` UInt64) \
+             AS SELECT id FROM default.events e JOIN (SELECT 1) USING id",
+            "default",
+        )
+        .unwrap();
+        assert!(compare(&file, &server).is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+use super::SYSTEM_DATABASES;
+use crate::client::{self, Client};
+use crate::error::{Error, Result};
+use crate::init;
+use crate::sql::ObjectName;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct ServerObject {
+    database: String,
+    name: String,
+    engine: String,
+    /// 1 when the object is listed in system.dictionaries. A table with
+    /// `ENGINE = Dictionary(...)` also reports the Dictionary engine, so the
+    /// engine alone can't tell the two apart.
+    is_dictionary: u8,
+}
+
+impl ServerObject {
+    /// Returns the scaffold subdirectory and the keyword used by SHOW CREATE
+    fn placement(&self) -> (&'static str, &'static str) {
+        if self.is_dictionary != 0 {
+            return ("dictionaries", "DICTIONARY");
+        }
+        match self.engine.as_str() {
+            "MaterializedView" => ("materialized_views", "TABLE"),
+            "View" => ("views", "TABLE"),
+            _ => ("tables", "TABLE"),
+        }
+    }
+
+    /// File name for the object: `events.sql` in the default database, `db.events.sql` elsewhere
+    fn file_name(&self) -> String {
+        if self.database == "default" {
+            format!("{}.sql", self.name)
+        } else {
+            format!("{}.{}.sql", self.database, self.name)
+        }
+    }
+}
+
+/// Adds `IF NOT EXISTS` so dumped files can be re-applied with `chv schema apply`
+fn make_idempotent(ddl: &str) -> String {
+    for prefix in [
+        "CREATE TABLE ",
+        "CREATE MATERIALIZED VIEW ",
+        "CREATE VIEW ",
+        "CREATE DICTIONARY ",
+    ] {
+        if let Some(rest) = ddl.strip_prefix(prefix)
+            && !rest.starts_with("IF NOT EXISTS")
+        {
+            return format!("{}IF NOT EXISTS {}", prefix, rest);
+        }
+    }
+    ddl.to_string()
+}
+
+/// Writes one formatted file per table, view, materialized view and dictionary on the server
+/// into the matching clickhouse/ subdirectory. Existing files that differ are left alone
+/// unless `force` is set.
+pub fn dump(client: &Client, version: &str, database: Option<&str>, force: bool) -> Result<()> {
+    let filter = match database {
+        Some(db) => format!(
+            "database = '{}'",
+            db.replace('\\', "\\\\").replace('\'', "\\'")
+        ),
+        None => format!(
+            "database NOT IN ({})",
+            SYSTEM_DATABASES
+                .iter()
+                .map(|d| format!("'{}'", d))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let output = client.query_with(
+        &format!(
+            "SELECT database, name, engine, \
+             (database, name) IN (SELECT database, name FROM system.dictionaries) AS is_dictionary \
+             FROM system.tables \
+             WHERE {} AND NOT is_temporary AND name NOT LIKE '.inner%' \
+             AND name != '{}' ORDER BY database, name",
+            filter,
+            crate::migrate::MIGRATIONS_TABLE
+        ),
+        &["--format=JSONEachRow".to_string()],
+    )?;
+    let objects: Vec<ServerObject> = output
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<std::result::Result<_, _>>()?;
+
+    if objects.is_empty() {
+        println!(
+            "No tables, views or dictionaries found on {}",
+            client.endpoint()
+        );
+        return Ok(());
+    }
+
+    let project = init::project_dir();
+    let (mut written, mut unchanged, mut skipped) = (0, 0, 0);
+    println!(
+        "Dumping {} objects from {} into {}/",
+        objects.len(),
+        client.endpoint(),
+        project.display()
+    );
+    for obj in &objects {
+        let (dir, keyword) = obj.placement();
+        let name = ObjectName {
+            database: Some(obj.database.clone()),
+            name: obj.name.clone(),
+        };
+        let ddl = client.query_with(
+            &format!("SHOW CREATE {} {}", keyword, name.quoted()),
+            &["--format=TSVRaw".to_string()],
+        )?;
+        let formatted = client::format_sql(version, ddl.trim(), &[]).map_err(|e| {
+            Error::Schema(format!(
+                "Failed to format the definition of {}: {}",
+                name, e
+            ))
+        })?;
+        let text = format!("{};\n", make_idempotent(formatted.trim()));
+
+        let rel = format!("{}/{}", dir, obj.file_name());
+        let path = project.join(&rel);
+        if path.exists() {
+            if std::fs::read_to_string(&path)? == text {
+                unchanged += 1;
+                continue;
+            }
+            if !force {
+                println!("  skipped {} (differs from the server; use --force)", rel);
+                skipped += 1;
+                continue;
+            }
+        }
+        std::fs::create_dir_all(project.join(dir))?;
+        std::fs::write(&path, text)?;
+        println!("  wrote   {}", rel);
+        written += 1;
+    }
+
+    println!(
+        "Dumped {} objects ({} written, {} unchanged, {} skipped)",
+        objects.len(),
+        written,
+        unchanged,
+        skipped
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_idempotent() {
+        assert_eq!(
+            make_idempotent("CREATE TABLE default.events\n(\n    `id` UInt64\n)"),
+            "CREATE TABLE IF NOT EXISTS default.events\n(\n    `id` UInt64\n)"
+        );
+        assert_eq!(
+            make_idempotent("CREATE VIEW IF NOT EXISTS v AS SELECT 1"),
+            "CREATE VIEW IF NOT EXISTS v AS SELECT 1"
+        );
+    }
+
+    #[test]
+    fn test_placement() {
+        let object = |engine: &str, is_dictionary| ServerObject {
+            database: "default".to_string(),
+            name: "d".to_string(),
+            engine: engine.to_string(),
+            is_dictionary,
+        };
+        assert_eq!(
+            object("Dictionary", 1).placement(),
+            ("dictionaries", "DICTIONARY")
+        );
+        assert_eq!(object("Dictionary", 0).placement(), ("tables", "TABLE"));
+        assert_eq!(object("View", 0).placement(), ("views", "TABLE"));
+    }
+}
@@ -0,0 +1,359 @@
+use super::{SYSTEM_DATABASES, load_project_files};
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::sql::{self, ObjectKind, ObjectName};
+use serde::{Deserialize, Serialize};
+
+/// A table, view or materialized view in the graph
+#[derive(Serialize)]
+struct Node {
+    name: String,
+    kind: String,
+    downstream: bool,
+}
+
+#[derive(Serialize, PartialEq)]
+struct Edge {
+    from: String,
+    to: String,
+    /// "reads" for a view selecting from a table, "writes" for a materialized view's TO table
+    kind: &'static str,
+}
+
+#[derive(Serialize)]
+struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+impl Graph {
+    fn new() -> Self {
+        Graph {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Adds a node unless present; a later definition fills in the kind of a referenced node
+    fn add_node(&mut self, name: &str, kind: Option<ObjectKind>) {
+        let kind = kind.map(|k| k.to_string());
+        match self.nodes.iter_mut().find(|n| n.name == name) {
+            Some(node) => {
+                if let Some(kind) = kind {
+                    node.kind = kind;
+                }
+            }
+            None => self.nodes.push(Node {
+                name: name.to_string(),
+                kind: kind.unwrap_or_else(|| "table".to_string()),
+                downstream: false,
+            }),
+        }
+    }
+
+    fn add_edge(&mut self, from: &str, to: &str, kind: &'static str) {
+        self.add_node(from, None);
+        self.add_node(to, None);
+        let edge = Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            kind,
+        };
+        if !self.edges.contains(&edge) {
+            self.edges.push(edge);
+        }
+    }
+
+    /// Marks `root` and every node reachable from it
+    fn mark_downstream(&mut self, root: &str) -> Result<()> {
+        let Some(start) = self.nodes.iter().position(|n| n.name == root) else {
+            return Err(Error::Schema(format!("{} is not in the graph", root)));
+        };
+        let mut stack = vec![start];
+        while let Some(i) = stack.pop() {
+            if self.nodes[i].downstream {
+                continue;
+            }
+            self.nodes[i].downstream = true;
+            let name = self.nodes[i].name.clone();
+            for edge in self.edges.iter().filter(|e| e.from == name) {
+                if let Some(j) = self.nodes.iter().position(|n| n.name == edge.to) {
+                    stack.push(j);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds the graph from the CREATE statements in the project files
+fn from_files() -> Result<Graph> {
+    let infos: Vec<_> = load_project_files()?
+        .into_iter()
+        .flat_map(|f| f.objects)
+        .filter(|o| o.kind != ObjectKind::Database)
+        .collect();
+
+    // Resolve a referenced name to the defined object it matches, so `db.t` and `t` meet
+    let resolve = |name: &ObjectName| -> String {
+        infos
+            .iter()
+            .find(|o| o.name.matches(name))
+            .map(|o| o.name.to_string())
+            .unwrap_or_else(|| name.to_string())
+    };
+
+    let mut graph = Graph::new();
+    for info in &infos {
+        graph.add_node(&info.name.to_string(), Some(info.kind));
+    }
+    for info in &infos {
+        if !matches!(info.kind, ObjectKind::View | ObjectKind::MaterializedView) {
+            continue;
+        }
+        let name = info.name.to_string();
+        for source in &info.sources {
+            graph.add_edge(&resolve(source), &name, "reads");
+        }
+        if let Some(to) = &info.to {
+            graph.add_edge(&name, &resolve(to), "writes");
+        }
+    }
+    Ok(graph)
+}
+
+#[derive(Deserialize)]
+struct ServerTable {
+    database: String,
+    name: String,
+    engine: String,
+    create_table_query: String,
+    dependencies_database: Vec<String>,
+    dependencies_table: Vec<String>,
+}
+
+/// Builds the graph from system.tables: `dependencies_*` lists the views reading from
+/// each table, and a materialized view's TO table comes from its definition
+fn from_server(client: &Client) -> Result<Graph> {
+    let excluded: Vec<String> = SYSTEM_DATABASES
+        .iter()
+        .map(|d| format!("'{}'", d))
+        .collect();
+    let output = client.query_with(
+        &format!(
+            "SELECT database, name, engine, create_table_query, dependencies_database, \
+             dependencies_table FROM system.tables \
+             WHERE database NOT IN ({}) AND NOT is_temporary ORDER BY database, name",
+            excluded.join(", ")
+        ),
+        &["--format=JSONEachRow".to_string()],
+    )?;
+
+    let mut graph = Graph::new();
+    let mut tables = Vec::new();
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        let table: ServerTable = serde_json::from_str(line)?;
+        let kind = match table.engine.as_str() {
+            "MaterializedView" => ObjectKind::MaterializedView,
+            "View" => ObjectKind::View,
+            "Dictionary" => ObjectKind::Dictionary,
+            _ => ObjectKind::Table,
+        };
+        graph.add_node(&format!("{}.{}", table.database, table.name), Some(kind));
+        tables.push(table);
+    }
+
+    for table in &tables {
+        let name = format!("{}.{}", table.database, table.name);
+        for (db, dependent) in table
+            .dependencies_database
+            .iter()
+            .zip(&table.dependencies_table)
+        {
+            graph.add_edge(&name, &format!("{}.{}", db, dependent), "reads");
+        }
+        if table.engine == "MaterializedView"
+            && let Some(to) = sql::parse_create(&table.create_table_query).and_then(|i| i.to)
+        {
+            let to = ObjectName {
+                database: Some(to.database.unwrap_or_else(|| table.database.clone())),
+                name: to.name,
+            };
+            graph.add_edge(&name, &to.to_string(), "writes");
+        }
+    }
+    Ok(graph)
+}
+
+/// Mermaid ID of a node, from its position so distinct names never collide
+fn node_id(graph: &Graph, name: &str) -> String {
+    let index = graph.nodes.iter().position(|n| n.name == name);
+    format!("n{}", index.expect("edges only join known nodes"))
+}
+
+/// Escapes a name for a quoted Mermaid label
+fn mermaid_label(name: &str) -> String {
+    name.replace('"', "#quot;")
+}
+
+/// Escapes a name for a quoted DOT string
+fn dot_label(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid(graph: &Graph) -> String {
+    let mut out = String::from("flowchart LR\n");
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let id = format!("n{}", i);
+        let label = mermaid_label(&node.name);
+        let shape = match node.kind.as_str() {
+            "materialized view" => format!("{}{{{{\"{}\"}}}}", id, label),
+            "view" => format!("{}(\"{}\")", id, label),
+            _ => format!("{}[\"{}\"]", id, label),
+        };
+        out.push_str(&format!("    {}\n", shape));
+    }
+    for edge in &graph.edges {
+        let arrow = if edge.kind == "writes" {
+            "-->|TO|"
+        } else {
+            "-->"
+        };
+        out.push_str(&format!(
+            "    {} {} {}\n",
+            node_id(graph, &edge.from),
+            arrow,
+            node_id(graph, &edge.to)
+        ));
+    }
+    let downstream: Vec<String> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, n)| n.downstream)
+        .map(|(i, _)| format!("n{}", i))
+        .collect();
+    if !downstream.is_empty() {
+        out.push_str("    classDef downstream fill:#fde68a,stroke:#d97706\n");
+        out.push_str(&format!("    class {} downstream\n", downstream.join(",")));
+    }
+    out
+}
+
+fn dot(graph: &Graph) -> String {
+    let mut out = String::from("digraph schema {\n    rankdir=LR;\n");
+    for node in &graph.nodes {
+        let shape = match node.kind.as_str() {
+            "materialized view" => "hexagon",
+            "view" => "ellipse",
+            _ => "box",
+        };
+        let style = if node.downstream {
+            ", style=filled, fillcolor=\"#fde68a\""
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "    \"{}\" [shape={}{}];\n",
+            dot_label(&node.name),
+            shape,
+            style
+        ));
+    }
+    for edge in &graph.edges {
+        let label = if edge.kind == "writes" {
+            " [label=\"TO\"]"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "    \"{}\" -> \"{}\"{};\n",
+            dot_label(&edge.from),
+            dot_label(&edge.to),
+            label
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Prints the table → view → table graph from project files, or from a server when given a client
+pub fn graph(client: Option<&Client>, format: &str, downstream: Option<&str>) -> Result<()> {
+    let mut graph = match client {
+        Some(client) => from_server(client)?,
+        None => from_files()?,
+    };
+
+    if let Some(root) = downstream {
+        // Accept an unqualified name when it identifies a single node
+        let matches: Vec<String> = graph
+            .nodes
+            .iter()
+            .filter(|n| n.name == root || n.name.rsplit('.').next() == Some(root))
+            .map(|n| n.name.clone())
+            .collect();
+        match matches.as_slice() {
+            [one] => graph.mark_downstream(one)?,
+            [] => {
+                return Err(Error::Schema(format!("{} is not in the graph", root)));
+            }
+            _ => {
+                return Err(Error::Schema(format!(
+                    "{} is ambiguous: {}",
+                    root,
+                    matches.join(", ")
+                )));
+            }
+        }
+    }
+
+    let output = match format {
+        "mermaid" => mermaid(&graph),
+        "dot" => dot(&graph),
+        "json" => serde_json::to_string_pretty(&graph)? + "\n",
+        other => {
+            return Err(Error::Schema(format!(
+                "Unknown graph format {} (expected mermaid, dot or json)",
+                other
+            )));
+        }
+    };
+    print!("{}", output);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mark_downstream() {
+        let mut graph = Graph::new();
+        graph.add_node("events", Some(ObjectKind::Table));
+        graph.add_edge("events", "daily_mv", "reads");
+        graph.add_edge("daily_mv", "daily", "writes");
+        graph.add_edge("users", "users_mv", "reads");
+        graph.mark_downstream("events").unwrap();
+        let marked: Vec<&str> = graph
+            .nodes
+            .iter()
+            .filter(|n| n.downstream)
+            .map(|n| n.name.as_str())
+            .collect();
+        assert_eq!(marked, vec!["events", "daily_mv", "daily"]);
+        assert!(mermaid(&graph).contains("class n0,n1,n2 downstream"));
+    }
+
+    #[test]
+    fn test_node_ids_and_labels() {
+        let mut graph = Graph::new();
+        graph.add_edge("a.b_c", "a_b.c", "reads");
+        graph.add_node("db.\"odd\"", Some(ObjectKind::Table));
+        let out = mermaid(&graph);
+        assert!(out.contains("n0[\"a.b_c\"]"), "{}", out);
+        assert!(out.contains("n1[\"a_b.c\"]"), "{}", out);
+        assert!(out.contains("n0 --> n1"), "{}", out);
+        assert!(out.contains("n2[\"db.#quot;odd#quot;\"]"), "{}", out);
+        assert!(dot(&graph).contains("\"db.\\\"odd\\\"\" [shape=box]"));
+    }
+}
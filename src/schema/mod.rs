@@ -0,0 +1,235 @@
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::init;
+use crate::sql::{self, CreateInfo, Statement};
+use std::path::{Path, PathBuf};
+
+mod diff;
+mod dump;
+mod graph;
+
+pub use diff::diff;
+pub use dump::dump;
+pub use graph::graph;
+
+/// Scaffold subdirectories holding object definitions, in apply order
+pub const SCHEMA_DIRS: &[&str] = &["tables", "dictionaries", "views", "materialized_views"];
+
+/// Server databases that never belong to a project
+pub(crate) const SYSTEM_DATABASES: &[&str] =
+    &["system", "information_schema", "INFORMATION_SCHEMA"];
+
+/// A SQL file from the project scaffold
+pub struct SchemaFile {
+    /// Path relative to the clickhouse/ project directory, for display
+    pub rel: String,
+    pub text: String,
+    pub statements: Vec<Statement>,
+    /// Objects defined by the file's CREATE statements
+    pub objects: Vec<CreateInfo>,
+}
+
+impl SchemaFile {
+    pub fn load(project: &Path, path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let statements = sql::split_statements(&text);
+        let objects = statements
+            .iter()
+            .filter_map(|s| sql::parse_create(&s.sql))
+            .collect();
+        let rel = path
+            .strip_prefix(project)
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        Ok(Self {
+            rel,
+            text,
+            statements,
+            objects,
+        })
+    }
+
+    /// Returns true if this file depends on an object defined by `other`
+    fn depends_on(&self, other: &SchemaFile) -> bool {
+        self.objects.iter().any(|obj| {
+            obj.to
+                .iter()
+                .chain(obj.sources.iter())
+                .any(|dep| other.objects.iter().any(|o| o.name.matches(dep)))
+        })
+    }
+}
+
+/// Lists `.sql` files in a directory, sorted by name
+pub fn sql_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|e| e == "sql") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Lists every `.sql` file below the clickhouse/ project directory, sorted by path
+pub fn project_sql_files() -> Result<Vec<PathBuf>> {
+    fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(&path, files)?;
+            } else if path.extension().is_some_and(|e| e == "sql") {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    let project = init::project_dir();
+    if project.exists() {
+        walk(&project, &mut files)?;
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Loads the table, dictionary and view definitions from the project scaffold
+pub fn load_project_files() -> Result<Vec<SchemaFile>> {
+    let project = init::project_dir();
+    let mut files = Vec::new();
+    for dir in SCHEMA_DIRS {
+        for path in sql_files(&project.join(dir))? {
+            files.push(SchemaFile::load(&project, &path)?);
+        }
+    }
+    Ok(files)
+}
+
+/// Orders files so every file comes after the files defining objects it reads from or writes to.
+/// Among files with no pending dependencies, the original order (tables first, then by name) wins.
+pub fn order_by_dependency(files: Vec<SchemaFile>) -> Result<Vec<SchemaFile>> {
+    let n = files.len();
+    let deps: Vec<Vec<usize>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .filter(|&j| j != i && files[i].depends_on(&files[j]))
+                .collect()
+        })
+        .collect();
+
+    let mut done = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    while order.len() < n {
+        let next = (0..n).find(|&i| !done[i] && deps[i].iter().all(|&d| done[d]));
+        match next {
+            Some(i) => {
+                done[i] = true;
+                order.push(i);
+            }
+            None => {
+                let cycle: Vec<&str> = (0..n)
+                    .filter(|&i| !done[i])
+                    .map(|i| files[i].rel.as_str())
+                    .collect();
+                return Err(Error::Schema(format!(
+                    "Circular dependency between: {}",
+                    cycle.join(", ")
+                )));
+            }
+        }
+    }
+
+    let mut slots: Vec<Option<SchemaFile>> = files.into_iter().map(Some).collect();
+    Ok(order.into_iter().filter_map(|i| slots[i].take()).collect())
+}
+
+/// Executes the project's definitions against a server in dependency order
+pub fn apply(client: &Client, dry_run: bool) -> Result<()> {
+    let files = order_by_dependency(load_project_files()?)?;
+    if files.is_empty() {
+        println!(
+            "No SQL files found in {}/{{{}}}",
+            init::project_dir().display(),
+            SCHEMA_DIRS.join(",")
+        );
+        return Ok(());
+    }
+
+    if dry_run {
+        println!("Apply order:");
+        for file in &files {
+            let objects: Vec<String> = file
+                .objects
+                .iter()
+                .map(|o| format!("{} {}", o.kind, o.name))
+                .collect();
+            println!("  {} ({})", file.rel, objects.join(", "));
+        }
+        return Ok(());
+    }
+
+    println!("Applying {} files to {}", files.len(), client.endpoint());
+    for file in &files {
+        if let Err(e) = client.execute_file(&file.rel, &file.text, &file.statements) {
+            println!("  failed  {}", file.rel);
+            return Err(e);
+        }
+        println!("  applied {}", file.rel);
+    }
+    println!("Applied {} files", files.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(rel: &str, text: &str) -> SchemaFile {
+        let statements = sql::split_statements(text);
+        let objects = statements
+            .iter()
+            .filter_map(|s| sql::parse_create(&s.sql))
+            .collect();
+        SchemaFile {
+            rel: rel.to_string(),
+            text: text.to_string(),
+            statements,
+            objects,
+        }
+    }
+
+    #[test]
+    fn test_order_by_dependency() {
+        let files = vec![
+            file("tables/a_rollup.sql", "CREATE TABLE rollup AS events_local"),
+            file(
+                "tables/b_events.sql",
+                "CREATE TABLE events_local (id UInt64) ENGINE = MergeTree ORDER BY id",
+            ),
+            file(
+                "materialized_views/mv.sql",
+                "CREATE MATERIALIZED VIEW mv TO rollup AS SELECT * FROM events_local",
+            ),
+        ];
+        let order: Vec<String> = order_by_dependency(files)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.rel)
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                "tables/b_events.sql",
+                "tables/a_rollup.sql",
+                "materialized_views/mv.sql"
+            ]
+        );
+    }
+}
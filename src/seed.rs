@@ -0,0 +1,250 @@
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::init;
+use crate::sql::{self, ObjectName};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Data file extensions and the ClickHouse input format used to load them.
+/// CSV and TSV files are expected to start with a header row naming the columns.
+const DATA_FORMATS: &[(&str, &str)] = &[
+    ("csv", "CSVWithNames"),
+    ("tsv", "TSVWithNames"),
+    ("jsonl", "JSONEachRow"),
+    ("parquet", "Parquet"),
+];
+
+/// Returns the seed directory (clickhouse/seed/)
+pub fn seed_dir() -> PathBuf {
+    init::project_dir().join("seed")
+}
+
+/// A file in the seed directory
+enum SeedFile {
+    /// SQL statements and the tables their INSERTs write to
+    Sql {
+        path: PathBuf,
+        text: String,
+        statements: Vec<sql::Statement>,
+        targets: Vec<ObjectName>,
+    },
+    /// A data file loaded into the table named after it
+    Data {
+        path: PathBuf,
+        table: ObjectName,
+        format: &'static str,
+    },
+}
+
+impl SeedFile {
+    fn path(&self) -> &Path {
+        match self {
+            SeedFile::Sql { path, .. } | SeedFile::Data { path, .. } => path,
+        }
+    }
+
+    fn targets(&self) -> Vec<ObjectName> {
+        match self {
+            SeedFile::Sql { targets, .. } => targets.clone(),
+            SeedFile::Data { table, .. } => vec![table.clone()],
+        }
+    }
+}
+
+/// Maps a data file name to its target table: `events.csv` → events,
+/// `analytics.events.csv` → analytics.events. The stem is used unchanged,
+/// so `2024_sales.csv` → 2024_sales.
+fn table_for(stem: &str) -> ObjectName {
+    match stem.split_once('.') {
+        Some((database, name)) => ObjectName {
+            database: Some(database.to_string()),
+            name: name.to_string(),
+        },
+        None => ObjectName {
+            database: None,
+            name: stem.to_string(),
+        },
+    }
+}
+
+/// Loads seed files in lexical order, skipping files with unknown extensions
+fn load_seed_files(dir: &Path) -> Result<Vec<SeedFile>> {
+    let mut paths = Vec::new();
+    if dir.exists() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+
+    let mut files = Vec::new();
+    for path in paths {
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some((stem, ext)) = file_name.rsplit_once('.') else {
+            continue;
+        };
+        let ext = ext.to_ascii_lowercase();
+
+        if ext == "sql" {
+            let text = std::fs::read_to_string(&path)?;
+            let statements = sql::split_statements(&text);
+            let mut targets: Vec<ObjectName> = Vec::new();
+            for target in statements
+                .iter()
+                .filter_map(|s| sql::parse_insert_target(&s.sql))
+            {
+                if !targets.contains(&target) {
+                    targets.push(target);
+                }
+            }
+            files.push(SeedFile::Sql {
+                path,
+                text,
+                statements,
+                targets,
+            });
+        } else if let Some((_, format)) = DATA_FORMATS.iter().find(|(e, _)| *e == ext) {
+            if stem.is_empty() {
+                return Err(Error::Seed(format!(
+                    "Cannot derive a table name from {}",
+                    path.display()
+                )));
+            }
+            files.push(SeedFile::Data {
+                table: table_for(stem),
+                format,
+                path,
+            });
+        }
+    }
+    Ok(files)
+}
+
+/// Returns the seed as SQL statements for a clickhouse-local session, loading
+/// data files with `INSERT ... FROM INFILE`
+pub fn local_statements() -> Result<Vec<String>> {
+    let mut statements = Vec::new();
+    for file in load_seed_files(&seed_dir())? {
+        match file {
+            SeedFile::Sql {
+                statements: stmts, ..
+            } => statements.extend(stmts.into_iter().map(|s| s.sql)),
+            SeedFile::Data {
+                path,
+                table,
+                format,
+            } => statements.push(format!(
+                "INSERT INTO {} FROM INFILE '{}' FORMAT {}",
+                table.quoted(),
+                path.display().to_string().replace('\'', "\\'"),
+                format
+            )),
+        }
+    }
+    Ok(statements)
+}
+
+fn count_rows(client: &Client, table: &ObjectName) -> Result<u64> {
+    let output = client.query(&format!("SELECT count() FROM {}", table.quoted()))?;
+    output.trim().parse().map_err(|_| {
+        Error::Seed(format!(
+            "Unexpected row count for {}: {}",
+            table,
+            output.trim()
+        ))
+    })
+}
+
+/// Runs seed SQL files and loads seed data files in lexical order, then prints
+/// the number of rows each touched table gained
+pub fn run(client: &Client, truncate: bool) -> Result<()> {
+    let dir = seed_dir();
+    let files = load_seed_files(&dir)?;
+    if files.is_empty() {
+        let exts: Vec<&str> = DATA_FORMATS.iter().map(|(e, _)| *e).collect();
+        println!(
+            "No seed files found in {} (.sql, .{})",
+            dir.display(),
+            exts.join(", .")
+        );
+        return Ok(());
+    }
+
+    let mut tables: Vec<ObjectName> = Vec::new();
+    for table in files.iter().flat_map(|f| f.targets()) {
+        if !tables.contains(&table) {
+            tables.push(table);
+        }
+    }
+
+    if truncate {
+        for table in &tables {
+            client.query(&format!("TRUNCATE TABLE IF EXISTS {}", table.quoted()))?;
+            println!("  truncated {}", table);
+        }
+    }
+
+    // Row counts before seeding; a table created by a seed file starts at zero
+    let mut before: BTreeMap<ObjectName, u64> = BTreeMap::new();
+    for table in &tables {
+        before.insert(table.clone(), count_rows(client, table).unwrap_or(0));
+    }
+
+    println!("Seeding {} files into {}", files.len(), client.endpoint());
+    for file in &files {
+        let rel = file
+            .path()
+            .strip_prefix(init::project_dir())
+            .unwrap_or(file.path())
+            .display()
+            .to_string();
+        let result = match file {
+            SeedFile::Sql {
+                text, statements, ..
+            } => client.execute_file(&rel, text, statements),
+            SeedFile::Data {
+                path,
+                table,
+                format,
+            } => client
+                .insert_file(&table.quoted(), format, path)
+                .map_err(|e| match e {
+                    Error::Query(m) => Error::Query(format!("{} → {}: {}", rel, table, m)),
+                    other => other,
+                }),
+        };
+        if let Err(e) = result {
+            println!("  failed {}", rel);
+            return Err(e);
+        }
+        println!("  loaded {}", rel);
+    }
+
+    println!("Rows inserted:");
+    for table in &tables {
+        let after = count_rows(client, table)?;
+        let inserted = after.saturating_sub(before[table]);
+        println!("  {:<30} {}", table.to_string(), inserted);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_for() {
+        assert_eq!(table_for("events").to_string(), "events");
+        assert_eq!(
+            table_for("analytics.page_views").to_string(),
+            "analytics.page_views"
+        );
+        assert_eq!(table_for("2024_sales").to_string(), "2024_sales");
+    }
+}
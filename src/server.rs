@@ -0,0 +1,158 @@
+use crate::cli::ConnectionArgs;
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::init;
+use crate::paths;
+use crate::process;
+use std::net::TcpListener;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Returns the PID of a server currently using the data directory, if any.
+/// ClickHouse writes its PID to a `status` file in the data path on startup.
+pub fn running_pid(data_dir: &Path) -> Option<u32> {
+    let status = std::fs::read_to_string(data_dir.join("status")).ok()?;
+    let pid = status
+        .lines()
+        .find_map(|l| l.strip_prefix("PID:"))?
+        .trim()
+        .parse()
+        .ok()?;
+    process::is_running(pid).then_some(pid)
+}
+
+/// Returns an error if a server is running against the version's data directory
+pub fn ensure_stopped(version: &str) -> Result<()> {
+    let dir = init::version_data_dir(version);
+    if let Some(pid) = running_pid(&dir) {
+        return Err(Error::Server(format!(
+            "ClickHouse server {} is running against {} (pid {}). Stop it first",
+            version,
+            dir.display(),
+            pid
+        )));
+    }
+    Ok(())
+}
+
+/// Finds a free local TCP port
+fn free_port() -> Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// A server started in the background on free ports, stopped on drop
+pub struct BackgroundServer {
+    client: Client,
+    data_dir: PathBuf,
+    child: Child,
+}
+
+impl BackgroundServer {
+    /// Starts a server for `version` against `data_dir` and waits until it accepts queries.
+    /// Data written by a newer version is refused unless `allow_downgrade` is set.
+    pub fn start(
+        version: &str,
+        data_dir: &Path,
+        allow_downgrade: bool,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let binary = paths::binary_path(version)?;
+        if !binary.exists() {
+            return Err(Error::VersionNotFound(version.to_string()));
+        }
+
+        init::open_data_dir(data_dir, version, allow_downgrade)?;
+
+        // Every listener needs its own port, or a `chv run server` on the defaults blocks startup
+        let tcp_port = free_port()?;
+        let http_port = free_port()?;
+        let mysql_port = free_port()?;
+        let postgresql_port = free_port()?;
+        let interserver_http_port = free_port()?;
+
+        let child = Command::new(&binary)
+            .arg("server")
+            .args(init::server_flags())
+            .arg("--logger.console=0")
+            .arg(format!("--tcp_port={}", tcp_port))
+            .arg(format!("--http_port={}", http_port))
+            .arg(format!("--mysql_port={}", mysql_port))
+            .arg(format!("--postgresql_port={}", postgresql_port))
+            .arg(format!("--interserver_http_port={}", interserver_http_port))
+            .current_dir(data_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .process_group(0)
+            .spawn()
+            .map_err(|e| Error::Exec(e.to_string()))?;
+
+        let conn = ConnectionArgs {
+            host: "127.0.0.1".into(),
+            port: tcp_port,
+            user: "default".into(),
+            password: None,
+            database: None,
+        };
+        let mut server = Self {
+            client: Client::new(version, &conn)?,
+            data_dir: data_dir.to_path_buf(),
+            child,
+        };
+
+        let start = Instant::now();
+        loop {
+            if let Some(status) = server.child.try_wait()? {
+                return Err(Error::Server(format!(
+                    "ClickHouse server {} exited during startup ({})\n{}",
+                    version,
+                    status,
+                    server.error_log_tail(10)
+                )));
+            }
+            if server.query("SELECT 1").is_ok() {
+                return Ok(server);
+            }
+            if start.elapsed() > timeout {
+                return Err(Error::Server(format!(
+                    "ClickHouse server {} did not become ready within {}s\n{}",
+                    version,
+                    timeout.as_secs(),
+                    server.error_log_tail(10)
+                )));
+            }
+            std::thread::sleep(Duration::from_millis(500));
+        }
+    }
+
+    /// Runs a query with clickhouse-client and returns its TSV output
+    pub fn query(&self, sql: &str) -> Result<String> {
+        self.client.query(sql)
+    }
+
+    /// Returns the last `n` lines of the server error log
+    pub fn error_log_tail(&self, n: usize) -> String {
+        let log =
+            std::fs::read_to_string(self.data_dir.join(init::SERVER_ERR_LOG)).unwrap_or_default();
+        let lines: Vec<&str> = log.lines().collect();
+        lines[lines.len().saturating_sub(n)..].join("\n")
+    }
+}
+
+impl Drop for BackgroundServer {
+    fn drop(&mut self) {
+        process::signal_term(self.child.id());
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(30) {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(200));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
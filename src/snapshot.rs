@@ -0,0 +1,306 @@
+use crate::data;
+use crate::error::{Error, Result};
+use crate::init;
+use crate::server;
+use crate::units::format_bytes;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Metadata stored alongside each snapshot
+#[derive(Serialize, Deserialize)]
+pub struct SnapshotMeta {
+    pub name: String,
+    pub version: String,
+    pub size_bytes: u64,
+    pub created_at: String,
+}
+
+/// Returns the snapshots directory inside a .clickhouse/ directory
+fn snapshots_dir(local: &Path) -> PathBuf {
+    local.join("snapshots")
+}
+
+fn snapshot_dir(local: &Path, name: &str) -> PathBuf {
+    snapshots_dir(local).join(name)
+}
+
+fn meta_path(local: &Path, name: &str) -> PathBuf {
+    snapshot_dir(local, name).join("snapshot.json")
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(Error::Snapshot(format!(
+            "Invalid snapshot name: {} (use letters, digits, '-', '_' and '.')",
+            name
+        )));
+    }
+    Ok(())
+}
+
+fn load_meta(local: &Path, name: &str) -> Result<SnapshotMeta> {
+    let path = meta_path(local, name);
+    if !path.exists() {
+        return Err(Error::Snapshot(format!("Snapshot {} not found", name)));
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// Saves the version's data directory as a named snapshot
+pub fn save(version: &str, name: &str, force: bool) -> Result<()> {
+    let meta = save_in(&init::local_dir(), version, name, force)?;
+    println!(
+        "Saved snapshot {} of {} ({})",
+        name,
+        version,
+        format_bytes(meta.size_bytes)
+    );
+    Ok(())
+}
+
+fn save_in(local: &Path, version: &str, name: &str, force: bool) -> Result<SnapshotMeta> {
+    validate_name(name)?;
+
+    let src = local.join(version);
+    if !src.exists() {
+        return Err(Error::Snapshot(format!(
+            "No data directory for version {} at {}",
+            version,
+            src.display()
+        )));
+    }
+
+    let dir = snapshot_dir(local, name);
+    if dir.exists() {
+        if !force {
+            return Err(Error::Snapshot(format!(
+                "Snapshot {} already exists. Use --force to overwrite it",
+                name
+            )));
+        }
+        std::fs::remove_dir_all(&dir)?;
+    }
+
+    if let Some(pid) = server::running_pid(&src) {
+        println!(
+            "Warning: server (pid {}) is running; the snapshot may not be consistent",
+            pid
+        );
+    }
+
+    std::fs::create_dir_all(&dir)?;
+    let stats = match data::copy_data_dir(&src, &dir.join("data"), true) {
+        Ok(stats) => stats,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&dir);
+            return Err(e);
+        }
+    };
+
+    let meta = SnapshotMeta {
+        name: name.to_string(),
+        version: version.to_string(),
+        size_bytes: stats.bytes,
+        created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    };
+    std::fs::write(meta_path(local, name), serde_json::to_string_pretty(&meta)?)?;
+    Ok(meta)
+}
+
+/// Replaces the version's data directory with a snapshot taken from the same version
+pub fn restore(version: &str, name: &str) -> Result<()> {
+    server::ensure_stopped(version)?;
+    let target = restore_in(&init::local_dir(), version, name)?;
+    println!("Restored snapshot {} into {}", name, target.display());
+    Ok(())
+}
+
+fn restore_in(local: &Path, version: &str, name: &str) -> Result<PathBuf> {
+    validate_name(name)?;
+    let meta = load_meta(local, name)?;
+
+    if meta.version != version {
+        return Err(Error::Snapshot(format!(
+            "Snapshot {} was taken with version {}, but the current version is {}. Run: chv use {}",
+            name, meta.version, version, meta.version
+        )));
+    }
+
+    let target = local.join(version);
+    let previous = local.join(format!(".{}.previous", version));
+    let _ = std::fs::remove_dir_all(&previous);
+
+    // Keep the current data aside until the snapshot has been copied in full
+    if target.exists() {
+        std::fs::rename(&target, &previous)?;
+    }
+    if let Err(e) = data::copy_data_dir(&snapshot_dir(local, name).join("data"), &target, true) {
+        let _ = std::fs::rename(&previous, &target);
+        return Err(e);
+    }
+    let _ = std::fs::remove_dir_all(&previous);
+    Ok(target)
+}
+
+/// Lists all snapshots, newest first
+pub fn list() -> Result<()> {
+    let snapshots = load_all(&init::local_dir())?;
+
+    if snapshots.is_empty() {
+        println!("No snapshots found");
+        println!("Run: chv snapshot save <name>");
+        return Ok(());
+    }
+
+    println!("Snapshots:");
+    for s in snapshots {
+        println!(
+            "  {} - {} ({}) {}",
+            s.name,
+            s.version,
+            format_bytes(s.size_bytes),
+            s.created_at
+        );
+    }
+    Ok(())
+}
+
+/// Reads the metadata of every snapshot, newest first
+fn load_all(local: &Path) -> Result<Vec<SnapshotMeta>> {
+    let dir = snapshots_dir(local);
+    let mut snapshots = Vec::new();
+    if dir.exists() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            if let Some(name) = entry.file_name().to_str()
+                && let Ok(meta) = load_meta(local, name)
+            {
+                snapshots.push(meta);
+            }
+        }
+    }
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(snapshots)
+}
+
+/// Deletes a snapshot
+pub fn remove(name: &str) -> Result<()> {
+    remove_in(&init::local_dir(), name)?;
+    println!("Removed snapshot {}", name);
+    Ok(())
+}
+
+fn remove_in(local: &Path, name: &str) -> Result<()> {
+    validate_name(name)?;
+    let dir = snapshot_dir(local, name);
+    if !dir.exists() {
+        return Err(Error::Snapshot(format!("Snapshot {} not found", name)));
+    }
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::MetadataExt;
+
+    const VERSION: &str = "25.1.1.1";
+    const PART_FILE: &str = "store/abc/abc123/all_1_1_0/data.bin";
+
+    /// A .clickhouse/ directory holding one version's data with a single part file
+    fn local_dir(name: &str) -> PathBuf {
+        let local =
+            std::env::temp_dir().join(format!("chv-snapshot-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&local);
+        let part = local.join(VERSION).join(PART_FILE);
+        std::fs::create_dir_all(part.parent().unwrap()).unwrap();
+        std::fs::write(part.with_file_name("checksums.txt"), "").unwrap();
+        std::fs::write(&part, "original").unwrap();
+        local
+    }
+
+    fn part(local: &Path) -> String {
+        std::fs::read_to_string(local.join(VERSION).join(PART_FILE)).unwrap()
+    }
+
+    #[test]
+    fn test_save_hardlinks_parts_and_refuses_overwrite() {
+        let local = local_dir("save");
+        let meta = save_in(&local, VERSION, "base", false).unwrap();
+        assert_eq!(meta.version, VERSION);
+        assert_eq!(meta.size_bytes, "original".len() as u64);
+
+        let src = std::fs::metadata(local.join(VERSION).join(PART_FILE)).unwrap();
+        let copy =
+            std::fs::metadata(snapshot_dir(&local, "base").join("data").join(PART_FILE)).unwrap();
+        assert_eq!(src.ino(), copy.ino());
+
+        assert!(save_in(&local, VERSION, "base", false).is_err());
+        assert!(save_in(&local, VERSION, "base", true).is_ok());
+        assert!(save_in(&local, VERSION, "../escape", false).is_err());
+        assert!(save_in(&local, "24.8.1.1", "other", false).is_err());
+        let _ = std::fs::remove_dir_all(&local);
+    }
+
+    #[test]
+    fn test_restore_replaces_data_and_checks_version() {
+        let local = local_dir("restore");
+        save_in(&local, VERSION, "base", false).unwrap();
+
+        // Parts are replaced, not rewritten, so a new file stands in for a new part
+        let part_path = local.join(VERSION).join(PART_FILE);
+        std::fs::remove_file(&part_path).unwrap();
+        std::fs::write(&part_path, "changed").unwrap();
+
+        let err = restore_in(&local, "24.8.1.1", "base").unwrap_err();
+        assert!(err.to_string().contains("chv use 25.1.1.1"), "{}", err);
+        assert_eq!(part(&local), "changed");
+
+        restore_in(&local, VERSION, "base").unwrap();
+        assert_eq!(part(&local), "original");
+        assert!(!local.join(format!(".{}.previous", VERSION)).exists());
+        let _ = std::fs::remove_dir_all(&local);
+    }
+
+    #[test]
+    fn test_restore_keeps_current_data_when_copy_fails() {
+        let local = local_dir("restore-fail");
+        save_in(&local, VERSION, "base", false).unwrap();
+        std::fs::remove_dir_all(snapshot_dir(&local, "base").join("data")).unwrap();
+
+        assert!(restore_in(&local, VERSION, "base").is_err());
+        assert_eq!(part(&local), "original");
+        assert!(!local.join(format!(".{}.previous", VERSION)).exists());
+        let _ = std::fs::remove_dir_all(&local);
+    }
+
+    #[test]
+    fn test_list_and_remove() {
+        let local = local_dir("list");
+        assert!(load_all(&local).unwrap().is_empty());
+        save_in(&local, VERSION, "first", false).unwrap();
+        save_in(&local, VERSION, "second", false).unwrap();
+        let names: Vec<String> = load_all(&local)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        assert_eq!(names.len(), 2);
+
+        remove_in(&local, "first").unwrap();
+        let names: Vec<String> = load_all(&local)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        assert_eq!(names, vec!["second"]);
+        assert!(remove_in(&local, "first").is_err());
+        let _ = std::fs::remove_dir_all(&local);
+    }
+}
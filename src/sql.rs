@@ -0,0 +1,717 @@
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Bare word: keyword or unquoted identifier
+    Word,
+    /// `identifier` or "identifier"
+    QuotedIdent,
+    /// 'string literal'
+    String,
+    Number,
+    Punct,
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+impl Token {
+    /// Returns true if this is a bare word matching `kw` case-insensitively
+    pub fn is_kw(&self, kw: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(kw)
+    }
+
+    pub fn is_punct(&self, p: &str) -> bool {
+        self.kind == TokenKind::Punct && self.text == p
+    }
+
+    pub fn is_ident(&self) -> bool {
+        matches!(self.kind, TokenKind::Word | TokenKind::QuotedIdent)
+    }
+}
+
+/// Splits text into tokens, skipping whitespace and comments
+pub fn tokenize(sql: &str) -> Vec<Token> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '-' && chars.get(i + 1) == Some(&'-') || c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+        } else if c == '\'' || c == '"' || c == '`' {
+            let mut text = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    text.push(chars[i + 1]);
+                    i += 2;
+                    continue;
+                }
+                text.push(chars[i]);
+                i += 1;
+            }
+            i += 1;
+            let kind = if c == '\'' {
+                TokenKind::String
+            } else {
+                TokenKind::QuotedIdent
+            };
+            tokens.push(Token { kind, text });
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token {
+                kind: TokenKind::Word,
+                text: chars[start..i].iter().collect(),
+            });
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(Token {
+                kind: TokenKind::Number,
+                text: chars[start..i].iter().collect(),
+            });
+        } else {
+            tokens.push(Token {
+                kind: TokenKind::Punct,
+                text: c.to_string(),
+            });
+            i += 1;
+        }
+    }
+    tokens
+}
+
+/// A single statement from a SQL file
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub sql: String,
+    /// 1-based line where the statement starts
+    pub line: usize,
+    /// Byte range of `sql` in the file text
+    pub span: Range<usize>,
+}
+
+/// Splits a file into statements on `;`, respecting quotes and comments.
+/// Statements that contain only comments are dropped.
+pub fn split_statements(text: &str) -> Vec<Statement> {
+    let chars: Vec<char> = text.chars().collect();
+    let bytes: Vec<usize> = text
+        .char_indices()
+        .map(|(b, _)| b)
+        .chain([text.len()])
+        .collect();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut current = String::new();
+    let mut start_line: Option<usize> = None;
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        // Comments before a statement starts are dropped so its first line is `line`
+        if c == '-' && chars.get(i + 1) == Some(&'-') || c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                if start_line.is_some() {
+                    current.push(chars[i]);
+                }
+                i += 1;
+            }
+            continue;
+        }
+        if c == '/' && chars.get(i + 1) == Some(&'*') {
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                if start_line.is_some() {
+                    current.push(chars[i]);
+                }
+                i += 1;
+            }
+            if start_line.is_some() {
+                current.push_str("*/");
+            }
+            i += 2;
+            continue;
+        }
+        if c == '\'' || c == '"' || c == '`' {
+            if start_line.is_none() {
+                start_line = Some(line);
+                start = i;
+            }
+            current.push(c);
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    current.push(chars[i]);
+                    i += 1;
+                }
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                current.push(chars[i]);
+                i += 1;
+            }
+            if i < chars.len() {
+                current.push(c);
+            }
+            i += 1;
+            continue;
+        }
+        if c == ';' {
+            if let Some(l) = start_line.take() {
+                statements.push(statement(&current, l, start, &bytes));
+            }
+            current.clear();
+            i += 1;
+            continue;
+        }
+
+        if c == '\n' {
+            line += 1;
+        } else if !c.is_whitespace() && start_line.is_none() {
+            start_line = Some(line);
+            start = i;
+        }
+        if start_line.is_some() {
+            current.push(c);
+        }
+        i += 1;
+    }
+
+    if let Some(l) = start_line {
+        statements.push(statement(&current, l, start, &bytes));
+    }
+    statements
+}
+
+/// Builds a statement from the raw text collected since char index `start`
+fn statement(current: &str, line: usize, start: usize, bytes: &[usize]) -> Statement {
+    let sql = current.trim();
+    let end = (start + sql.chars().count()).min(bytes.len() - 1);
+    Statement {
+        sql: sql.to_string(),
+        line,
+        span: bytes[start]..bytes[end],
+    }
+}
+
+/// Returns true if the SQL contains a comment outside string literals and quoted identifiers
+pub fn has_comment(sql: &str) -> bool {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '-' && chars.get(i + 1) == Some(&'-')
+            || c == '#'
+            || c == '/' && chars.get(i + 1) == Some(&'*')
+        {
+            return true;
+        }
+        if c == '\'' || c == '"' || c == '`' {
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Returns the index of the `)` closing the `(` at `tokens[0]`
+pub fn matching_paren(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0;
+    for (i, t) in tokens.iter().enumerate() {
+        if t.is_punct("(") {
+            depth += 1;
+        } else if t.is_punct(")") {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Splits tokens on commas outside parentheses
+pub fn split_top_level(tokens: &[Token]) -> Vec<&[Token]> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, t) in tokens.iter().enumerate() {
+        if t.is_punct("(") {
+            depth += 1;
+        } else if t.is_punct(")") {
+            depth -= 1;
+        } else if t.is_punct(",") && depth == 0 {
+            parts.push(&tokens[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&tokens[start..]);
+    parts
+}
+
+/// A query parameter placeholder such as `{start:Date}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryParam {
+    pub name: String,
+    pub ty: String,
+}
+
+/// Returns the query parameters declared in `sql`, in order of first use
+pub fn query_params(sql: &str) -> Vec<QueryParam> {
+    let tokens = tokenize(sql);
+    let mut params: Vec<QueryParam> = Vec::new();
+    let mut i = 0;
+    while i + 3 < tokens.len() {
+        if !(tokens[i].is_punct("{") && tokens[i + 1].is_ident() && tokens[i + 2].is_punct(":")) {
+            i += 1;
+            continue;
+        }
+        let mut ty = String::new();
+        let mut j = i + 3;
+        while j < tokens.len() && !tokens[j].is_punct("}") {
+            match tokens[j].kind {
+                TokenKind::String => ty.push_str(&format!("'{}'", tokens[j].text)),
+                _ if tokens[j].is_punct(",") => ty.push_str(", "),
+                _ => ty.push_str(&tokens[j].text),
+            }
+            j += 1;
+        }
+        let name = tokens[i + 1].text.clone();
+        if j < tokens.len() && !ty.is_empty() && !params.iter().any(|p| p.name == name) {
+            params.push(QueryParam { name, ty });
+        }
+        i = j + 1;
+    }
+    params
+}
+
+/// Maps a ClickHouse error's "(line N, col M)" position to a line in the file
+pub fn error_line(stmt: &Statement, message: &str) -> usize {
+    message
+        .find("(line ")
+        .and_then(|p| {
+            let rest = &message[p + 6..];
+            rest[..rest.find(',')?].parse::<usize>().ok()
+        })
+        .map(|rel| stmt.line + rel - 1)
+        .unwrap_or(stmt.line)
+}
+
+/// An optionally database-qualified object name
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectName {
+    pub database: Option<String>,
+    pub name: String,
+}
+
+impl ObjectName {
+    /// Returns true if both names refer to the same object. An unqualified name
+    /// matches any database, since the default database is only known at runtime.
+    pub fn matches(&self, other: &ObjectName) -> bool {
+        self.name == other.name
+            && match (&self.database, &other.database) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            }
+    }
+
+    /// Returns the name with each part backquoted, for use in generated SQL
+    pub fn quoted(&self) -> String {
+        let quote = |s: &str| format!("`{}`", s.replace('\\', "\\\\").replace('`', "\\`"));
+        match &self.database {
+            Some(db) => format!("{}.{}", quote(db), quote(&self.name)),
+            None => quote(&self.name),
+        }
+    }
+}
+
+impl fmt::Display for ObjectName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.database {
+            Some(db) => write!(f, "{}.{}", db, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectKind {
+    Database,
+    Table,
+    View,
+    MaterializedView,
+    Dictionary,
+}
+
+impl fmt::Display for ObjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ObjectKind::Database => "database",
+            ObjectKind::Table => "table",
+            ObjectKind::View => "view",
+            ObjectKind::MaterializedView => "materialized view",
+            ObjectKind::Dictionary => "dictionary",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// What a `CREATE` statement defines and depends on
+#[derive(Debug, Clone)]
+pub struct CreateInfo {
+    pub kind: ObjectKind,
+    pub name: ObjectName,
+    /// Target table of a materialized view (`TO db.table`)
+    pub to: Option<ObjectName>,
+    /// Tables read via FROM/JOIN, or copied with `CREATE TABLE t AS other`
+    pub sources: Vec<ObjectName>,
+}
+
+/// Parses a (possibly qualified) name starting at `tokens[i]`, returning it and the next index
+pub fn parse_name(tokens: &[Token], i: usize) -> Option<(ObjectName, usize)> {
+    let first = tokens.get(i).filter(|t| t.is_ident())?;
+    if tokens.get(i + 1).is_some_and(|t| t.is_punct("."))
+        && let Some(second) = tokens.get(i + 2).filter(|t| t.is_ident())
+    {
+        return Some((
+            ObjectName {
+                database: Some(first.text.clone()),
+                name: second.text.clone(),
+            },
+            i + 3,
+        ));
+    }
+    Some((
+        ObjectName {
+            database: None,
+            name: first.text.clone(),
+        },
+        i + 1,
+    ))
+}
+
+/// Returns the table an `INSERT INTO [TABLE] name ...` statement writes to
+pub fn parse_insert_target(sql: &str) -> Option<ObjectName> {
+    let tokens = tokenize(sql);
+    if !tokens.first()?.is_kw("INSERT") || !tokens.get(1)?.is_kw("INTO") {
+        return None;
+    }
+    let i = if tokens.get(2)?.is_kw("TABLE") { 3 } else { 2 };
+    // INSERT INTO FUNCTION ... writes to a table function, not a table
+    if tokens.get(i)?.is_kw("FUNCTION") {
+        return None;
+    }
+    parse_name(&tokens, i).map(|(name, _)| name)
+}
+
+/// Extracts the object defined by a `CREATE` statement and the objects it depends on.
+/// This is a token scan, not a full parser: it understands enough ClickHouse DDL to order
+/// project files and report on them.
+pub fn parse_create(sql: &str) -> Option<CreateInfo> {
+    parse_create_parts(sql).map(|(info, _)| info)
+}
+
+/// Like [`parse_create`], but also returns the tokens after the statement header
+/// (`CREATE ... name [ON CLUSTER c] [UUID u]`), i.e. the column list, `TO`, `AS` and engine clauses
+pub fn parse_create_parts(sql: &str) -> Option<(CreateInfo, Vec<Token>)> {
+    let tokens = tokenize(sql);
+    let mut i = 0;
+
+    if !tokens.first()?.is_kw("CREATE") && !tokens.first()?.is_kw("ATTACH") {
+        return None;
+    }
+    i += 1;
+    if tokens.get(i).is_some_and(|t| t.is_kw("OR")) {
+        i += 2; // OR REPLACE
+    }
+    if tokens.get(i).is_some_and(|t| t.is_kw("TEMPORARY")) {
+        i += 1;
+    }
+
+    let kind = match tokens.get(i)?.text.to_ascii_uppercase().as_str() {
+        "DATABASE" => ObjectKind::Database,
+        "TABLE" => ObjectKind::Table,
+        "VIEW" => ObjectKind::View,
+        "DICTIONARY" => ObjectKind::Dictionary,
+        "MATERIALIZED" => {
+            i += 1;
+            ObjectKind::MaterializedView
+        }
+        "LIVE" | "WINDOW" => {
+            i += 1;
+            ObjectKind::View
+        }
+        _ => return None,
+    };
+    i += 1;
+
+    if tokens.get(i).is_some_and(|t| t.is_kw("IF")) {
+        i += 3; // IF NOT EXISTS
+    }
+
+    let (name, mut i) = parse_name(&tokens, i)?;
+
+    // Optional clauses between the name and the body
+    loop {
+        match tokens.get(i) {
+            Some(t) if t.is_kw("ON") => i += 3, // ON CLUSTER name
+            Some(t) if t.is_kw("UUID") => i += 2,
+            _ => break,
+        }
+    }
+
+    let mut to = None;
+    if kind == ObjectKind::MaterializedView
+        && tokens.get(i).is_some_and(|t| t.is_kw("TO"))
+        && let Some((target, _)) = parse_name(&tokens, i + 1)
+    {
+        to = Some(target);
+    }
+
+    let mut sources: Vec<ObjectName> = Vec::new();
+    for (j, t) in tokens.iter().enumerate() {
+        let is_source_kw = t.is_kw("FROM") || t.is_kw("JOIN");
+        // CREATE TABLE t AS other [ENGINE ...]
+        let is_table_as = kind == ObjectKind::Table && t.is_kw("AS") && j == i;
+        if !is_source_kw && !is_table_as {
+            continue;
+        }
+        let Some((source, next)) = parse_name(&tokens, j + 1) else {
+            continue;
+        };
+        // Skip table functions, subqueries and `AS SELECT`
+        if tokens.get(next).is_some_and(|t| t.is_punct("("))
+            || tokens[j + 1].is_kw("SELECT")
+            || tokens[j + 1].is_kw("WITH")
+        {
+            continue;
+        }
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+
+    let body = tokens.get(i..).unwrap_or_default().to_vec();
+    Some((
+        CreateInfo {
+            kind,
+            name,
+            to,
+            sources,
+        },
+        body,
+    ))
+}
+
+/// Keywords that keep a space before a following `(` when rendering
+const SPACED_KEYWORDS: &[&str] = &[
+    "AND", "AS", "BY", "ELSE", "EXISTS", "FROM", "IN", "JOIN", "KEY", "NOT", "ON", "OR", "SELECT",
+    "THEN", "TO", "USING", "VALUES", "WHEN", "WHERE",
+];
+
+/// Renders tokens back to SQL with canonical spacing, so statements that differ only
+/// in whitespace, comments or identifier quoting render the same
+pub fn render(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut prev: Option<&Token> = None;
+    for t in tokens {
+        let glued = t.is_punct(")")
+            || t.is_punct(",")
+            || t.is_punct(".")
+            || (t.is_punct("(")
+                && prev.is_some_and(|p| {
+                    p.kind == TokenKind::Word
+                        && !SPACED_KEYWORDS
+                            .iter()
+                            .any(|kw| p.text.eq_ignore_ascii_case(kw))
+                }))
+            || prev.is_some_and(|p| p.is_punct("(") || p.is_punct("."));
+        if prev.is_some() && !glued {
+            out.push(' ');
+        }
+        match t.kind {
+            TokenKind::String => {
+                out.push('\'');
+                out.push_str(&t.text.replace('\\', "\\\\").replace('\'', "\\'"));
+                out.push('\'');
+            }
+            TokenKind::QuotedIdent if needs_quoting(&t.text) => {
+                out.push('`');
+                out.push_str(&t.text);
+                out.push('`');
+            }
+            _ => out.push_str(&t.text),
+        }
+        prev = Some(t);
+    }
+    out
+}
+
+fn needs_quoting(ident: &str) -> bool {
+    ident.is_empty()
+        || ident.starts_with(|c: char| c.is_ascii_digit())
+        || !ident.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements() {
+        let text = "-- header\nCREATE TABLE a (s String DEFAULT ';') ENGINE = Memory;\n\n/* x; */\nCREATE TABLE b AS a;\n-- trailing comment\n";
+        let stmts = split_statements(text);
+        assert_eq!(stmts.len(), 2);
+        assert_eq!(stmts[0].line, 2);
+        assert!(stmts[0].sql.starts_with("CREATE TABLE a"));
+        assert!(stmts[0].sql.ends_with("ENGINE = Memory"));
+        assert_eq!(stmts[1].line, 5);
+        assert_eq!(stmts[1].sql, "CREATE TABLE b AS a");
+        for stmt in &stmts {
+            assert_eq!(&text[stmt.span.clone()], stmt.sql);
+        }
+        assert!(has_comment("SELECT 1 -- one"));
+        assert!(!has_comment("SELECT '--', `#x`"));
+    }
+
+    #[test]
+    fn test_parse_insert_target() {
+        let target = parse_insert_target("INSERT INTO TABLE db.events (id) VALUES (1)").unwrap();
+        assert_eq!(target.to_string(), "db.events");
+        assert_eq!(target.quoted(), "`db`.`events`");
+        assert_eq!(
+            parse_insert_target("insert into users SELECT * FROM numbers(3)")
+                .unwrap()
+                .to_string(),
+            "users"
+        );
+        assert!(parse_insert_target("INSERT INTO FUNCTION file('x.csv') VALUES (1)").is_none());
+        assert!(parse_insert_target("SELECT 1").is_none());
+    }
+
+    #[test]
+    fn test_query_params() {
+        let params = query_params(
+            "SELECT * FROM events -- {ignored:String}\n\
+             WHERE ts >= {start:DateTime64(3, 'UTC')} AND country IN {countries:Array(String)} \
+             AND note != '{not:Param}' AND ts < {start:DateTime64(3, 'UTC')}",
+        );
+        let declared: Vec<(&str, &str)> = params
+            .iter()
+            .map(|p| (p.name.as_str(), p.ty.as_str()))
+            .collect();
+        assert_eq!(
+            declared,
+            vec![
+                ("start", "DateTime64(3, 'UTC')"),
+                ("countries", "Array(String)")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let a = render(&tokenize(
+            "ENGINE = MergeTree()\n  ORDER BY (`id`, ts) -- key\n SETTINGS x = 'a'",
+        ));
+        assert_eq!(a, "ENGINE = MergeTree() ORDER BY (id, ts) SETTINGS x = 'a'");
+        assert_eq!(
+            render(&tokenize("`my col` Nullable( String )")),
+            "`my col` Nullable(String)"
+        );
+    }
+
+    #[test]
+    fn test_parse_create_mv() {
+        let info = parse_create(
+            "CREATE MATERIALIZED VIEW IF NOT EXISTS db.mv TO db.daily AS \
+             SELECT * FROM db.events e JOIN users u ON e.id = u.id \
+             WHERE x IN (SELECT y FROM numbers(10))",
+        )
+        .unwrap();
+        assert_eq!(info.kind, ObjectKind::MaterializedView);
+        assert_eq!(info.name.to_string(), "db.mv");
+        assert_eq!(info.to.unwrap().to_string(), "db.daily");
+        let sources: Vec<String> = info.sources.iter().map(|s| s.to_string()).collect();
+        assert_eq!(sources, vec!["db.events", "users"]);
+    }
+
+    #[test]
+    fn test_parse_create_table() {
+        let info = parse_create(
+            "CREATE TABLE `events` ON CLUSTER c (id UInt64) ENGINE = MergeTree ORDER BY id",
+        )
+        .unwrap();
+        assert_eq!(info.kind, ObjectKind::Table);
+        assert_eq!(info.name.to_string(), "events");
+        assert!(info.sources.is_empty());
+
+        let copy = parse_create("CREATE TABLE events_copy AS events").unwrap();
+        assert_eq!(copy.sources[0].name, "events");
+        assert!(parse_create("INSERT INTO t VALUES (1)").is_none());
+    }
+
+    #[test]
+    fn test_object_name_matches() {
+        let qualified = ObjectName {
+            database: Some("db".into()),
+            name: "t".into(),
+        };
+        let bare = ObjectName {
+            database: None,
+            name: "t".into(),
+        };
+        let other = ObjectName {
+            database: Some("other".into()),
+            name: "t".into(),
+        };
+        assert!(qualified.matches(&bare));
+        assert!(!qualified.matches(&other));
+    }
+
+    #[test]
+    fn test_error_line() {
+        let stmt = Statement {
+            sql: String::new(),
+            line: 10,
+            span: 0..0,
+        };
+        assert_eq!(
+            error_line(&stmt, "Syntax error: failed at position 5 (line 3, col 2)"),
+            12
+        );
+        assert_eq!(error_line(&stmt, "Code: 57. Table already exists"), 10);
+    }
+}
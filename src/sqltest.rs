@@ -0,0 +1,232 @@
+use crate::error::{Error, Result};
+use crate::init;
+use crate::paths;
+use crate::schema;
+use crate::seed;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Instant;
+
+/// Returns the tests directory (clickhouse/tests/)
+pub fn tests_dir() -> PathBuf {
+    init::project_dir().join("tests")
+}
+
+/// A test query and its expected output
+struct TestCase {
+    name: String,
+    sql: PathBuf,
+    reference: PathBuf,
+}
+
+/// Finds `NAME.sql` files in the tests directory, optionally limited to `names`
+fn discover(names: &[String]) -> Result<Vec<TestCase>> {
+    let dir = tests_dir();
+    let mut cases = Vec::new();
+    for sql in schema::sql_files(&dir)? {
+        let Some(name) = sql.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if !names.is_empty() && !names.iter().any(|n| n == name) {
+            continue;
+        }
+        cases.push(TestCase {
+            name: name.to_string(),
+            reference: dir.join(format!("{}.reference", name)),
+            sql,
+        });
+    }
+
+    for name in names {
+        if !cases.iter().any(|c| &c.name == name) {
+            return Err(Error::Test(format!(
+                "No test named {} in {}",
+                name,
+                dir.display()
+            )));
+        }
+    }
+    Ok(cases)
+}
+
+/// Builds the statements that recreate the project schema and seed data
+fn setup_script() -> Result<String> {
+    let mut script = String::new();
+    for file in schema::order_by_dependency(schema::load_project_files()?)? {
+        for stmt in &file.statements {
+            script.push_str(&stmt.sql);
+            script.push_str(";\n");
+        }
+    }
+    for stmt in seed::local_statements()? {
+        script.push_str(&stmt);
+        script.push_str(";\n");
+    }
+    Ok(script)
+}
+
+/// Runs one test in a fresh clickhouse-local session and returns its output
+fn run_case(binary: &Path, setup: &str, case: &TestCase) -> Result<String> {
+    let work = init::local_dir().join("tmp").join("tests").join(&case.name);
+    let _ = std::fs::remove_dir_all(&work);
+    std::fs::create_dir_all(&work)?;
+
+    let script = work.join("script.sql");
+    std::fs::write(
+        &script,
+        format!("{}{}\n", setup, std::fs::read_to_string(&case.sql)?),
+    )?;
+
+    let output = Command::new(binary)
+        .arg("local")
+        .arg("--path")
+        .arg(work.join("data"))
+        .arg("--queries-file")
+        .arg(&script)
+        .current_dir(tests_dir())
+        .output()?;
+    let _ = std::fs::remove_dir_all(&work);
+
+    if !output.status.success() {
+        return Err(Error::Test(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns a line diff of expected vs actual output, with `-`/`+` markers
+fn line_diff(expected: &str, actual: &str) -> Vec<String> {
+    let a: Vec<&str> = expected.lines().collect();
+    let b: Vec<&str> = actual.lines().collect();
+
+    // Longest common subsequence table
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            out.push(format!("+{}", b[j]));
+            j += 1;
+        } else {
+            out.push(format!("-{}", a[i]));
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Runs the tests in clickhouse/tests/, comparing each output with its `.reference` file.
+/// With `update`, writes the actual output as the new reference instead.
+pub fn run(version: &str, names: &[String], update: bool) -> Result<()> {
+    let binary = paths::binary_path(version)?;
+    if !binary.exists() {
+        return Err(Error::VersionNotFound(version.to_string()));
+    }
+
+    let cases = discover(names)?;
+    if cases.is_empty() {
+        println!("No tests found in {}", tests_dir().display());
+        println!(
+            "Add NAME.sql with the expected output in NAME.reference, or run `chv test --update`"
+        );
+        return Ok(());
+    }
+
+    init::ensure_local_dir()?;
+    let setup = setup_script()?;
+    let mut failed = Vec::new();
+    println!("Running {} tests with ClickHouse {}", cases.len(), version);
+
+    for case in &cases {
+        let started = Instant::now();
+        let result = run_case(&binary, &setup, case);
+        let elapsed = format!("{:.2}s", started.elapsed().as_secs_f64());
+
+        let actual = match result {
+            Ok(actual) => actual,
+            Err(e) => {
+                println!("  error   {} ({})", case.name, elapsed);
+                let message = match e {
+                    Error::Test(m) => m,
+                    other => other.to_string(),
+                };
+                for line in message.lines() {
+                    println!("      {}", line);
+                }
+                failed.push(case.name.clone());
+                continue;
+            }
+        };
+
+        let expected = std::fs::read_to_string(&case.reference).ok();
+        if update {
+            if expected.as_deref() == Some(actual.as_str()) {
+                println!("  ok      {} ({})", case.name, elapsed);
+            } else {
+                std::fs::write(&case.reference, &actual)?;
+                println!("  updated {} ({})", case.name, elapsed);
+            }
+            continue;
+        }
+
+        match expected {
+            Some(expected) if expected == actual => {
+                println!("  ok      {} ({})", case.name, elapsed);
+            }
+            Some(expected) => {
+                println!("  FAIL    {} ({})", case.name, elapsed);
+                let diff = line_diff(&expected, &actual);
+                if diff.is_empty() {
+                    println!("      (output differs only in line endings)");
+                }
+                for line in diff {
+                    println!("      {}", line);
+                }
+                failed.push(case.name.clone());
+            }
+            None => {
+                println!(
+                    "  FAIL    {} (no {}.reference; run with --update to create it)",
+                    case.name, case.name
+                );
+                failed.push(case.name.clone());
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(Error::Test(format!(
+            "{} of {} tests failed: {}",
+            failed.len(),
+            cases.len(),
+            failed.join(", ")
+        )));
+    }
+    println!("All {} tests passed", cases.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff() {
+        assert!(line_diff("1\n2\n", "1\n2\n").is_empty());
+        assert_eq!(line_diff("a\nb\nc\n", "a\nc\nd\n"), vec!["-b", "+d"]);
+    }
+}
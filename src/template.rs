@@ -0,0 +1,185 @@
+use crate::error::{Error, Result};
+use crate::init;
+use std::path::Path;
+
+/// A project template bundled with chv
+pub struct Template {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Paths relative to clickhouse/ and their contents
+    files: &'static [(&'static str, &'static str)],
+}
+
+/// Embeds a template file from src/templates/{template}/{path}
+macro_rules! template_file {
+    ($template:literal, $path:literal) => {
+        (
+            $path,
+            include_str!(concat!("templates/", $template, "/", $path)),
+        )
+    };
+}
+
+/// Templates available to `chv init --template <name>`
+pub const BUILTIN: &[Template] = &[
+    Template {
+        name: "events",
+        description: "Event analytics: raw events, daily rollup with unique users, DAU queries",
+        files: &[
+            template_file!("events", "tables/events.sql"),
+            template_file!("events", "tables/events_daily.sql"),
+            template_file!("events", "materialized_views/events_daily_mv.sql"),
+            template_file!("events", "queries/daily_active_users.sql"),
+            template_file!("events", "queries/top_events.sql"),
+            template_file!("events", "seed/events.csv"),
+            template_file!("events", "tests/daily_active_users.reference"),
+            template_file!("events", "tests/daily_active_users.sql"),
+            template_file!("events", "tests/events_by_type.reference"),
+            template_file!("events", "tests/events_by_type.sql"),
+        ],
+    },
+    Template {
+        name: "logs",
+        description: "Logs and observability: log records, per-minute level counts, search queries",
+        files: &[
+            template_file!("logs", "tables/log_levels_per_minute.sql"),
+            template_file!("logs", "tables/logs.sql"),
+            template_file!("logs", "materialized_views/log_levels_per_minute_mv.sql"),
+            template_file!("logs", "queries/errors_by_service.sql"),
+            template_file!("logs", "queries/search_logs.sql"),
+            template_file!("logs", "seed/logs.jsonl"),
+            template_file!("logs", "tests/errors_by_service.reference"),
+            template_file!("logs", "tests/errors_by_service.sql"),
+            template_file!("logs", "tests/search_logs.reference"),
+            template_file!("logs", "tests/search_logs.sql"),
+        ],
+    },
+    Template {
+        name: "metrics",
+        description: "Time-series metrics: raw samples, hourly min/max/avg rollup, range queries",
+        files: &[
+            template_file!("metrics", "tables/metrics.sql"),
+            template_file!("metrics", "tables/metrics_1h.sql"),
+            template_file!("metrics", "materialized_views/metrics_1h_mv.sql"),
+            template_file!("metrics", "queries/latest_values.sql"),
+            template_file!("metrics", "queries/metric_hourly.sql"),
+            template_file!("metrics", "seed/metrics.csv"),
+            template_file!("metrics", "tests/hourly_rollup.reference"),
+            template_file!("metrics", "tests/hourly_rollup.sql"),
+        ],
+    },
+];
+
+/// Writes a file into the project unless it already exists. Returns true if written.
+fn write_new(project: &Path, rel: &str, contents: &[u8]) -> Result<bool> {
+    let path = project.join(rel);
+    if path.exists() {
+        println!("  skipped {} (already exists)", rel);
+        return Ok(false);
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, contents)?;
+    println!("  created {}", rel);
+    Ok(true)
+}
+
+/// Collects files below a local template directory, skipping hidden entries
+fn collect_files(dir: &Path, prefix: &str, files: &mut Vec<String>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        let rel = format!("{}{}", prefix, name);
+        if entry.file_type()?.is_dir() {
+            collect_files(&entry.path(), &format!("{}/", rel), files)?;
+        } else {
+            files.push(rel);
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `spec` names a built-in template or an existing directory
+pub fn validate(spec: &str) -> Result<()> {
+    if Path::new(spec).is_dir() || BUILTIN.iter().any(|t| t.name == spec) {
+        return Ok(());
+    }
+    let names: Vec<&str> = BUILTIN.iter().map(|t| t.name).collect();
+    Err(Error::Template(format!(
+        "Unknown template {}. Built-in templates: {} (or pass a path to a directory)",
+        spec,
+        names.join(", ")
+    )))
+}
+
+/// Copies a template into the clickhouse/ project directory without overwriting files.
+/// `spec` is a built-in template name or a path to a directory laid out like clickhouse/.
+pub fn apply(spec: &str) -> Result<()> {
+    let project = init::project_dir();
+    let local = Path::new(spec);
+
+    let written = if local.is_dir() {
+        let mut files = Vec::new();
+        collect_files(local, "", &mut files)?;
+        files.sort();
+        println!("Applying template from {}", local.display());
+        let mut written = 0;
+        for rel in &files {
+            if write_new(&project, rel, &std::fs::read(local.join(rel))?)? {
+                written += 1;
+            }
+        }
+        written
+    } else if let Some(template) = BUILTIN.iter().find(|t| t.name == spec) {
+        println!("Applying template {}", template.name);
+        let mut written = 0;
+        for (rel, contents) in template.files {
+            if write_new(&project, rel, contents.as_bytes())? {
+                written += 1;
+            }
+        }
+        written
+    } else {
+        return validate(spec);
+    };
+
+    println!("Wrote {} files into {}/", written, project.display());
+    Ok(())
+}
+
+/// Prints the built-in templates
+pub fn list() {
+    println!("Built-in templates:");
+    for t in BUILTIN {
+        println!("  {:<10} {}", t.name, t.description);
+    }
+    println!("Use a directory laid out like clickhouse/ as a template with --template <path>");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_templates_have_tests_with_references() {
+        for t in BUILTIN {
+            for (rel, _) in t
+                .files
+                .iter()
+                .filter(|(p, _)| p.ends_with(".sql") && p.starts_with("tests/"))
+            {
+                let reference = rel.replace(".sql", ".reference");
+                assert!(
+                    t.files.iter().any(|(p, _)| *p == reference),
+                    "{}: {} has no reference",
+                    t.name,
+                    rel
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,8 @@
+CREATE MATERIALIZED VIEW IF NOT EXISTS events_daily_mv TO events_daily AS
+SELECT
+    toDate(event_time) AS day,
+    event_type,
+    count() AS events,
+    uniqState(user_id) AS users
+FROM events
+GROUP BY day, event_type;
@@ -0,0 +1,8 @@
+-- Daily active users between two dates
+SELECT
+    day,
+    uniqMerge(users) AS active_users
+FROM events_daily
+WHERE day BETWEEN {start:Date} AND {end:Date}
+GROUP BY day
+ORDER BY day;
@@ -0,0 +1,9 @@
+-- Most frequent event types on a day
+SELECT
+    event_type,
+    sum(events) AS events
+FROM events_daily
+WHERE day = {day:Date}
+GROUP BY event_type
+ORDER BY events DESC
+LIMIT {limit:UInt32};
@@ -0,0 +1,12 @@
+-- Raw product events, one row per event
+CREATE TABLE IF NOT EXISTS events
+(
+    event_time DateTime64(3),
+    event_type LowCardinality(String),
+    user_id UInt64,
+    session_id String,
+    page String
+)
+ENGINE = MergeTree
+PARTITION BY toYYYYMM(event_time)
+ORDER BY (event_type, event_time, user_id);
@@ -0,0 +1,10 @@
+-- Daily rollup of events per type, filled by materialized_views/events_daily_mv.sql
+CREATE TABLE IF NOT EXISTS events_daily
+(
+    day Date,
+    event_type LowCardinality(String),
+    events UInt64,
+    users AggregateFunction(uniq, UInt64)
+)
+ENGINE = AggregatingMergeTree
+ORDER BY (day, event_type);
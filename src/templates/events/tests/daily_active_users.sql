@@ -0,0 +1,4 @@
+SELECT day, uniqMerge(users)
+FROM events_daily
+GROUP BY day
+ORDER BY day;
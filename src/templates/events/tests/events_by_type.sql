@@ -0,0 +1,4 @@
+SELECT event_type, sum(events)
+FROM events_daily
+GROUP BY event_type
+ORDER BY event_type;
@@ -0,0 +1,8 @@
+CREATE MATERIALIZED VIEW IF NOT EXISTS log_levels_per_minute_mv TO log_levels_per_minute AS
+SELECT
+    toStartOfMinute(timestamp) AS minute,
+    service,
+    level,
+    count() AS count
+FROM logs
+GROUP BY minute, service, level;
@@ -0,0 +1,8 @@
+-- Error counts per service since a point in time
+SELECT
+    service,
+    sum(count) AS errors
+FROM log_levels_per_minute
+WHERE level = 'error' AND minute >= {since:DateTime}
+GROUP BY service
+ORDER BY errors DESC;
@@ -0,0 +1,6 @@
+-- Latest records containing a word
+SELECT timestamp, service, level, message
+FROM logs
+WHERE hasToken(message, {term:String})
+ORDER BY timestamp DESC
+LIMIT {limit:UInt32};
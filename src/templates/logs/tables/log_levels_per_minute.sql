@@ -0,0 +1,10 @@
+-- Record counts per service and level per minute, filled by materialized_views/log_levels_per_minute_mv.sql
+CREATE TABLE IF NOT EXISTS log_levels_per_minute
+(
+    minute DateTime,
+    service LowCardinality(String),
+    level LowCardinality(String),
+    count UInt64
+)
+ENGINE = SummingMergeTree
+ORDER BY (service, level, minute);
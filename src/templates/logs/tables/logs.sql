@@ -0,0 +1,15 @@
+-- Application logs, one row per record
+CREATE TABLE IF NOT EXISTS logs
+(
+    timestamp DateTime64(3),
+    service LowCardinality(String),
+    level LowCardinality(String),
+    message String,
+    trace_id String,
+    attributes Map(LowCardinality(String), String),
+    INDEX idx_message message TYPE tokenbf_v1(32768, 3, 0) GRANULARITY 1
+)
+ENGINE = MergeTree
+PARTITION BY toDate(timestamp)
+ORDER BY (service, level, timestamp);
+-- To expire old records, add for example: TTL toDateTime(timestamp) + INTERVAL 30 DAY
@@ -0,0 +1,5 @@
+SELECT service, sum(count)
+FROM log_levels_per_minute
+WHERE level = 'error'
+GROUP BY service
+ORDER BY service;
@@ -0,0 +1,4 @@
+SELECT trace_id
+FROM logs
+WHERE hasToken(message, 'timeout')
+ORDER BY timestamp;
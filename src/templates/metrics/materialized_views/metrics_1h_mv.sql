@@ -0,0 +1,10 @@
+CREATE MATERIALIZED VIEW IF NOT EXISTS metrics_1h_mv TO metrics_1h AS
+SELECT
+    metric,
+    toStartOfHour(timestamp) AS hour,
+    min(value) AS min,
+    max(value) AS max,
+    sum(value) AS sum,
+    count() AS count
+FROM metrics
+GROUP BY metric, hour;
@@ -0,0 +1,8 @@
+-- Most recent sample of every metric
+SELECT
+    metric,
+    argMax(value, timestamp) AS value,
+    max(timestamp) AS at
+FROM metrics
+GROUP BY metric
+ORDER BY metric;
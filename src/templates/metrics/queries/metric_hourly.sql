@@ -0,0 +1,10 @@
+-- Hourly min/avg/max of one metric over a time range
+SELECT
+    hour,
+    min(min) AS min,
+    sum(sum) / sum(count) AS avg,
+    max(max) AS max
+FROM metrics_1h
+WHERE metric = {metric:String} AND hour BETWEEN {start:DateTime} AND {end:DateTime}
+GROUP BY hour
+ORDER BY hour;
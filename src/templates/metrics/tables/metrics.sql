@@ -0,0 +1,11 @@
+-- Raw metric samples
+CREATE TABLE IF NOT EXISTS metrics
+(
+    metric LowCardinality(String),
+    timestamp DateTime,
+    value Float64,
+    tags Map(LowCardinality(String), String)
+)
+ENGINE = MergeTree
+PARTITION BY toYYYYMM(timestamp)
+ORDER BY (metric, timestamp);
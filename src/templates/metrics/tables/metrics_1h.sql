@@ -0,0 +1,12 @@
+-- Hourly min/max/sum/count per metric, filled by materialized_views/metrics_1h_mv.sql
+CREATE TABLE IF NOT EXISTS metrics_1h
+(
+    metric LowCardinality(String),
+    hour DateTime,
+    min SimpleAggregateFunction(min, Float64),
+    max SimpleAggregateFunction(max, Float64),
+    sum SimpleAggregateFunction(sum, Float64),
+    count SimpleAggregateFunction(sum, UInt64)
+)
+ENGINE = AggregatingMergeTree
+ORDER BY (metric, hour);
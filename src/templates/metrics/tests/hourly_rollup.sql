@@ -0,0 +1,4 @@
+SELECT metric, hour, min(min), max(max), sum(sum) / sum(count)
+FROM metrics_1h
+GROUP BY metric, hour
+ORDER BY metric, hour;
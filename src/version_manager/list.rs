@@ -13,13 +13,13 @@ pub fn list_installed_versions() -> Result<Vec<String>> {
     let mut versions = Vec::new();
     for entry in std::fs::read_dir(&versions_dir)? {
         let entry = entry?;
-        if entry.path().is_dir() {
-            if let Some(name) = entry.file_name().to_str() {
-                // Only include if it has a clickhouse binary
-                let binary = entry.path().join("clickhouse");
-                if binary.exists() {
-                    versions.push(name.to_string());
-                }
+        if entry.path().is_dir()
+            && let Some(name) = entry.file_name().to_str()
+        {
+            // Only include if it has a clickhouse binary
+            let binary = entry.path().join("clickhouse");
+            if binary.exists() {
+                versions.push(name.to_string());
             }
         }
     }
@@ -110,7 +110,7 @@ pub fn set_default_version(version: &str) -> Result<()> {
 }
 
 /// Compares two version strings for sorting
-fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
     let a_parts: Vec<u64> = a.split('.').filter_map(|s| s.parse().ok()).collect();
     let b_parts: Vec<u64> = b.split('.').filter_map(|s| s.parse().ok()).collect();
 
@@ -4,5 +4,8 @@ pub mod list;
 pub mod resolve;
 
 pub use install::install_version;
-pub use list::{get_default_version, list_available_versions, list_installed_versions, set_default_version};
+pub use list::{
+    compare_versions, get_default_version, list_available_versions, list_installed_versions,
+    set_default_version,
+};
 pub use resolve::resolve_version;
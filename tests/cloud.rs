@@ -0,0 +1,838 @@
+//! Runs `chv cloud` commands against a local mock of the Cloud API.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A canned response for one method and path
+struct Route {
+    method: &'static str,
+    path: &'static str,
+    /// Served in order; the last one repeats
+    responses: VecDeque<(u16, String)>,
+}
+
+/// A recorded request: method, path and body
+type Request = (String, String, String);
+
+struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    fn start(routes: Vec<Route>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let routes = Arc::new(Mutex::new(routes));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handle(stream, &routes, &recorded);
+            }
+        });
+        MockServer { url, requests }
+    }
+
+    fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn handle(stream: TcpStream, routes: &Mutex<Vec<Route>>, requests: &Mutex<Vec<Request>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut line = String::new();
+    if reader.read_line(&mut line).is_err() {
+        return;
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).is_err() || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut body = vec![0; length];
+    let _ = reader.read_exact(&mut body);
+    let body = String::from_utf8_lossy(&body).into_owned();
+
+    let path = path.strip_prefix("/v1").unwrap_or(&path).to_string();
+    let (status, response) = {
+        let mut routes = routes.lock().unwrap();
+        match routes
+            .iter_mut()
+            .find(|r| r.method == method && r.path == path)
+        {
+            Some(route) if route.responses.len() > 1 => route.responses.pop_front().unwrap(),
+            Some(route) => route.responses[0].clone(),
+            None => (404, r#"{"error":{"message":"No mock route"}}"#.to_string()),
+        }
+    };
+    requests.lock().unwrap().push((method, path, body));
+
+    let mut stream = stream;
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nRetry-After: 0\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        response.len(),
+        response
+    );
+}
+
+fn route(method: &'static str, path: &'static str, responses: &[(u16, &str)]) -> Route {
+    Route {
+        method,
+        path,
+        responses: responses.iter().map(|(s, b)| (*s, b.to_string())).collect(),
+    }
+}
+
+/// An empty working directory, so no project credentials file is picked up
+fn work_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("chv-cloud-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn chv(server: &MockServer, name: &str, args: &[&str]) -> Output {
+    chv_in(&work_dir(name), server, args)
+}
+
+/// Runs chv in `dir`, which also serves as HOME so user-wide settings stay isolated
+fn chv_in(dir: &PathBuf, server: &MockServer, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_chv"))
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env_remove("CLICKHOUSE_CLOUD_API_URL")
+        .env_remove("CLICKHOUSE_CLOUD_ORG_ID")
+        .args(["cloud", "--api-key", "key", "--api-secret", "secret"])
+        .args(["--api-url", &server.url])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+const SERVICE: &str = r#"{"id":"6a1b7c9d-0000-4000-8000-000000000001","name":"analytics","provider":"aws","region":"us-east-1","state":"running"}"#;
+
+#[test]
+fn org_list_success() {
+    let server = MockServer::start(vec![route(
+        "GET",
+        "/organizations",
+        &[(200, r#"{"result":[{"id":"org-1","name":"Acme"}]}"#)],
+    )]);
+    let output = chv(&server, "org-list", &["org", "list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Acme (org-1)"));
+}
+
+#[test]
+fn service_get_json() {
+    let server = MockServer::start(vec![route(
+        "GET",
+        "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+        &[(200, &format!(r#"{{"result":{}}}"#, SERVICE))],
+    )]);
+    let output = chv(
+        &server,
+        "service-get-json",
+        &[
+            "--json",
+            "service",
+            "get",
+            "6a1b7c9d-0000-4000-8000-000000000001",
+            "--org-id",
+            "org-1",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let value: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(value["name"], "analytics");
+}
+
+#[test]
+fn api_error_body() {
+    let server = MockServer::start(vec![route(
+        "GET",
+        "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-00000000dead",
+        &[(
+            404,
+            r#"{"status":404,"requestId":"req-1","error":{"code":"NOT_FOUND","message":"Service not found"}}"#,
+        )],
+    )]);
+    let output = chv(
+        &server,
+        "api-error",
+        &[
+            "service",
+            "get",
+            "6a1b7c9d-0000-4000-8000-00000000dead",
+            "--org-id",
+            "org-1",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(4));
+    assert!(
+        stderr(&output).contains("Service not found (HTTP 404, code NOT_FOUND, request id req-1)"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn json_error_object() {
+    let server = MockServer::start(vec![route(
+        "GET",
+        "/organizations",
+        &[(
+            401,
+            r#"{"status":401,"requestId":"req-2","error":{"code":"UNAUTHORIZED","message":"Invalid API key"}}"#,
+        )],
+    )]);
+    let output = chv(&server, "json-error", &["--json", "org", "list"]);
+    assert_eq!(output.status.code(), Some(3));
+    let value: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({"error": {
+            "message": "Invalid API key",
+            "exit_code": 3,
+            "status": 401,
+            "code": "UNAUTHORIZED",
+            "request_id": "req-2",
+        }})
+    );
+}
+
+#[test]
+fn malformed_json() {
+    let server = MockServer::start(vec![route(
+        "GET",
+        "/organizations",
+        &[(200, r#"{"result":[{"id":"org-1""#)],
+    )]);
+    let output = chv(&server, "malformed", &["org", "list"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("Failed to parse response"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn empty_result() {
+    let server = MockServer::start(vec![route(
+        "GET",
+        "/organizations/org-1",
+        &[(200, r#"{"result":null}"#)],
+    )]);
+    let output = chv(&server, "empty", &["org", "get", "org-1"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("Empty response from API"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn retries_idempotent_call() {
+    let server = MockServer::start(vec![route(
+        "GET",
+        "/organizations",
+        &[
+            (503, "unavailable"),
+            (200, r#"{"result":[{"id":"org-1","name":"Acme"}]}"#),
+        ],
+    )]);
+    let output = chv(&server, "retry", &["org", "list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[test]
+fn does_not_retry_create() {
+    let server = MockServer::start(vec![route(
+        "POST",
+        "/organizations/org-1/services",
+        &[(503, "unavailable")],
+    )]);
+    let output = chv(
+        &server,
+        "create",
+        &[
+            "service",
+            "create",
+            "--name",
+            "analytics",
+            "--org-id",
+            "org-1",
+        ],
+    );
+    assert!(!output.status.success());
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    let body: serde_json::Value = serde_json::from_str(&requests[0].2).unwrap();
+    assert_eq!(body["name"], "analytics");
+}
+
+/// A get/state-change response body for 6a1b7c9d-0000-4000-8000-000000000001 in the given state
+fn service_in(state: &str) -> String {
+    format!(
+        r#"{{"result":{}}}"#,
+        SERVICE.replace(r#""state":"running""#, &format!(r#""state":"{}""#, state))
+    )
+}
+
+#[test]
+fn start_waits_until_running() {
+    let server = MockServer::start(vec![
+        route(
+            "PATCH",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001/state",
+            &[(200, &service_in("starting"))],
+        ),
+        route(
+            "GET",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(200, &service_in("running"))],
+        ),
+    ]);
+    let output = chv(
+        &server,
+        "start-wait",
+        &[
+            "service",
+            "start",
+            "6a1b7c9d-0000-4000-8000-000000000001",
+            "--org-id",
+            "org-1",
+            "--wait",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Service analytics is running"));
+}
+
+#[test]
+fn wait_fails_on_failed_state() {
+    let server = MockServer::start(vec![
+        route(
+            "PATCH",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001/state",
+            &[(200, &service_in("starting"))],
+        ),
+        route(
+            "GET",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(200, &service_in("failed"))],
+        ),
+    ]);
+    let output = chv(
+        &server,
+        "start-failed",
+        &[
+            "service",
+            "start",
+            "6a1b7c9d-0000-4000-8000-000000000001",
+            "--org-id",
+            "org-1",
+            "--wait",
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("entered state failed while waiting for it to be running"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn delete_waits_until_gone() {
+    let server = MockServer::start(vec![
+        route(
+            "DELETE",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(200, &service_in("terminating"))],
+        ),
+        route(
+            "GET",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(
+                404,
+                r#"{"error":{"code":"NOT_FOUND","message":"Service not found"}}"#,
+            )],
+        ),
+    ]);
+    let output = chv(
+        &server,
+        "delete-wait",
+        &[
+            "service",
+            "delete",
+            "6a1b7c9d-0000-4000-8000-000000000001",
+            "--org-id",
+            "org-1",
+            "--wait",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Service 6a1b7c9d-0000-4000-8000-000000000001 deleted"));
+}
+
+const TWO_ORGS: &str =
+    r#"{"result":[{"id":"org-1","name":"Acme"},{"id":"org-2","name":"Globex"}]}"#;
+
+#[test]
+fn several_orgs_require_a_choice() {
+    let server = MockServer::start(vec![route("GET", "/organizations", &[(200, TWO_ORGS)])]);
+    let output = chv(&server, "two-orgs", &["service", "list"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("can access 2 organizations: Acme (org-1), Globex (org-2)"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn org_use_persists_choice() {
+    let server = MockServer::start(vec![
+        route("GET", "/organizations", &[(200, TWO_ORGS)]),
+        route(
+            "GET",
+            "/organizations/org-2/services",
+            &[(200, &format!(r#"{{"result":[{}]}}"#, SERVICE))],
+        ),
+    ]);
+    let dir = work_dir("org-use");
+    let output = chv_in(&dir, &server, &["org", "use", "globex"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(dir.join(".clickhouse/cloud.json").exists());
+
+    let output = chv_in(&dir, &server, &["service", "list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("analytics (6a1b7c9d-0000-4000-8000-000000000001)"));
+
+    let output = chv_in(&dir, &server, &["auth", "status"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("Organization: Globex (org-2), from"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn service_by_name() {
+    let server = MockServer::start(vec![
+        route(
+            "GET",
+            "/organizations/org-1/services",
+            &[(200, &format!(r#"{{"result":[{}]}}"#, SERVICE))],
+        ),
+        route(
+            "GET",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(200, &service_in("running"))],
+        ),
+    ]);
+    for reference in ["analytics", "6a1b7c"] {
+        let output = chv(
+            &server,
+            "by-name",
+            &["service", "get", reference, "--org-id", "org-1"],
+        );
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert!(stdout(&output).contains("analytics"));
+    }
+}
+
+#[test]
+fn update_shows_changes() {
+    let after = SERVICE.replace(
+        r#""name":"analytics""#,
+        r#""name":"events","tags":[{"key":"env","value":"prod"}]"#,
+    );
+    let server = MockServer::start(vec![
+        route(
+            "GET",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(200, &service_in("running"))],
+        ),
+        route(
+            "PATCH",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(200, &format!(r#"{{"result":{}}}"#, after))],
+        ),
+    ]);
+    let output = chv(
+        &server,
+        "update",
+        &[
+            "service",
+            "update",
+            "6a1b7c9d-0000-4000-8000-000000000001",
+            "--org-id",
+            "org-1",
+            "--name",
+            "events",
+            "--add-tag",
+            "env=prod",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("name: analytics → events"));
+    assert!(stdout(&output).contains("+ tag env=prod"));
+
+    let requests = server.requests();
+    let patch = requests.iter().find(|r| r.0 == "PATCH").unwrap();
+    let body: serde_json::Value = serde_json::from_str(&patch.2).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({"name": "events", "tags": {"add": [{"key": "env", "value": "prod"}]}})
+    );
+}
+
+#[test]
+fn scale_validates_before_sending() {
+    let server = MockServer::start(vec![]);
+    let output = chv(
+        &server,
+        "scale-invalid",
+        &[
+            "service",
+            "scale",
+            "6a1b7c9d-0000-4000-8000-000000000001",
+            "--org-id",
+            "org-1",
+            "--max-replica-memory-gb",
+            "30",
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("multiple of 4"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn scale_waits_for_new_settings() {
+    let scaled = SERVICE.replace(
+        r#""state":"running""#,
+        r#""state":"running","numReplicas":3"#,
+    );
+    let server = MockServer::start(vec![
+        route(
+            "GET",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[
+                (200, &service_in("running")),
+                (200, &format!(r#"{{"result":{}}}"#, scaled)),
+            ],
+        ),
+        route(
+            "PATCH",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001/replicaScaling",
+            &[(200, &service_in("running"))],
+        ),
+    ]);
+    let output = chv(
+        &server,
+        "scale-wait",
+        &[
+            "service",
+            "scale",
+            "6a1b7c9d-0000-4000-8000-000000000001",
+            "--org-id",
+            "org-1",
+            "--num-replicas",
+            "3",
+            "--wait",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("replicas: - → 3"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn reset_password_saves_secret() {
+    let with_endpoint = SERVICE.replace(
+        r#""state":"running""#,
+        r#""state":"running","endpoints":[{"protocol":"https","host":"abc.clickhouse.cloud","port":8443},{"protocol":"nativesecure","host":"abc.clickhouse.cloud","port":9440}]"#,
+    );
+    let server = MockServer::start(vec![
+        route(
+            "GET",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(200, &format!(r#"{{"result":{}}}"#, with_endpoint))],
+        ),
+        route(
+            "PATCH",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001/password",
+            &[(200, r#"{"result":{"password":"s3cret-pw"}}"#)],
+        ),
+    ]);
+    let dir = work_dir("reset-password");
+    let output = chv_in(
+        &dir,
+        &server,
+        &[
+            "service",
+            "reset-password",
+            "6a1b7c9d-0000-4000-8000-000000000001",
+            "--org-id",
+            "org-1",
+            "--save",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("s3cret-pw"), "{}", stdout(&output));
+
+    let path = dir.join(".clickhouse/secrets.json");
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.contains(r#""password": "s3cret-pw""#), "{}", saved);
+    assert!(saved.contains(r#""port": 9440"#), "{}", saved);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[test]
+fn ip_set_replaces_open_access_with_my_ip() {
+    let open = SERVICE.replace(
+        r#""state":"running""#,
+        r#""state":"running","ipAccessList":[{"source":"0.0.0.0/0","description":"Allow all"}]"#,
+    );
+    let restricted = SERVICE.replace(
+        r#""state":"running""#,
+        r#""state":"running","ipAccessList":[{"source":"10.0.0.0/8"},{"source":"203.0.113.7/32","description":"Added by chv --add-my-ip"}]"#,
+    );
+    let server = MockServer::start(vec![
+        route(
+            "GET",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(200, &format!(r#"{{"result":{}}}"#, open))],
+        ),
+        route(
+            "PATCH",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(200, &format!(r#"{{"result":{}}}"#, restricted))],
+        ),
+        route("GET", "/my-ip", &[(200, "203.0.113.7\n")]),
+    ]);
+    let my_ip_url = format!("{}/my-ip", server.url);
+    let output = chv(
+        &server,
+        "ip-set",
+        &[
+            "service",
+            "ip",
+            "set",
+            "6a1b7c9d-0000-4000-8000-000000000001",
+            "10.0.0.0/8",
+            "--org-id",
+            "org-1",
+            "--add-my-ip",
+            "--my-ip-url",
+            &my_ip_url,
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let out = stdout(&output);
+    assert!(out.contains("- ip 0.0.0.0/0 (Allow all)"), "{}", out);
+    assert!(out.contains("+ ip 203.0.113.7/32"), "{}", out);
+
+    let requests = server.requests();
+    let (_, _, body) = requests.iter().find(|(m, _, _)| m == "PATCH").unwrap();
+    let body: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(
+        body["ipAccessList"]["remove"][0]["source"],
+        serde_json::json!("0.0.0.0/0")
+    );
+    assert_eq!(
+        body["ipAccessList"]["add"][1]["source"],
+        serde_json::json!("203.0.113.7/32")
+    );
+}
+
+#[test]
+fn ip_add_rejects_invalid_cidr() {
+    let server = MockServer::start(vec![]);
+    let output = chv(
+        &server,
+        "ip-invalid",
+        &[
+            "service",
+            "ip",
+            "add",
+            "6a1b7c9d-0000-4000-8000-000000000001",
+            "10.0.0.0/40",
+            "--org-id",
+            "org-1",
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("Invalid prefix length"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn create_wait_failure_still_shows_password() {
+    let created = format!(
+        r#"{{"result":{{"service":{},"password":"first-pw"}}}}"#,
+        SERVICE.replace(r#""state":"running""#, r#""state":"provisioning""#)
+    );
+    let server = MockServer::start(vec![
+        route("POST", "/organizations/org-1/services", &[(200, &created)]),
+        route(
+            "GET",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(200, &service_in("failed"))],
+        ),
+    ]);
+    let args = [
+        "service",
+        "create",
+        "--name",
+        "analytics",
+        "--org-id",
+        "org-1",
+        "--wait",
+    ];
+    let output = chv(&server, "create-wait-failed", &args);
+    assert!(!output.status.success());
+    assert!(
+        stdout(&output).contains("Password: first-pw"),
+        "{}",
+        stdout(&output)
+    );
+
+    let output = chv(
+        &server,
+        "create-wait-failed-json",
+        &[&["--json"], &args[..]].concat(),
+    );
+    assert!(!output.status.success());
+    let value: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    let message = value["error"]["message"].as_str().unwrap();
+    assert!(message.contains("first-pw"), "{}", message);
+    assert!(
+        message.contains("6a1b7c9d-0000-4000-8000-000000000001"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn update_reports_applied_changes_when_second_patch_fails() {
+    let renamed = SERVICE.replace(r#""name":"analytics""#, r#""name":"events""#);
+    let server = MockServer::start(vec![
+        route(
+            "GET",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(200, &service_in("running"))],
+        ),
+        route(
+            "PATCH",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001",
+            &[(200, &format!(r#"{{"result":{}}}"#, renamed))],
+        ),
+        route(
+            "PATCH",
+            "/organizations/org-1/services/6a1b7c9d-0000-4000-8000-000000000001/replicaScaling",
+            &[(
+                400,
+                r#"{"error":{"message":"Idle scaling is not available"}}"#,
+            )],
+        ),
+    ]);
+    let args = [
+        "service",
+        "update",
+        "6a1b7c9d-0000-4000-8000-000000000001",
+        "--org-id",
+        "org-1",
+        "--name",
+        "events",
+        "--idle-scaling",
+        "false",
+    ];
+    let output = chv(&server, "update-partial", &args);
+    assert!(!output.status.success());
+    let err = stderr(&output);
+    assert!(
+        err.contains("Applied to service events before the error:"),
+        "{}",
+        err
+    );
+    assert!(err.contains("name: analytics → events"), "{}", err);
+    assert!(err.contains("Idle scaling is not available"), "{}", err);
+}
+
+#[test]
+fn update_validates_idle_timeout_before_sending() {
+    let server = MockServer::start(vec![]);
+    let output = chv(
+        &server,
+        "update-idle-timeout",
+        &[
+            "service",
+            "update",
+            "6a1b7c9d-0000-4000-8000-000000000001",
+            "--org-id",
+            "org-1",
+            "--idle-timeout-minutes",
+            "2",
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("--idle-timeout-minutes must be at least 5"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(server.requests().is_empty());
+}